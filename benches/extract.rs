@@ -0,0 +1,107 @@
+//! Benchmarks palette extraction with the default options, at each histogram precision, and with more colors.
+//!
+//! To compare against another revision, save a baseline there with `cargo bench -- --save-baseline before`, then run `cargo bench -- --baseline before` here.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use palette_extract::{
+    get_palette_rgb, get_palette_with_options, ColorAverage, MaxColors, PaletteExtractor,
+    PixelEncoding, PixelFilter, Quality,
+};
+
+const WIDTH: usize = 512;
+const HEIGHT: usize = 512;
+
+/// A smooth gradient with some noise, so the pixels cover a large part of the color space like a photo.
+fn photo() -> Vec<u8> {
+    let mut seed: u32 = 0x9e37_79b9;
+    let mut noise = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        (seed % 32) as usize
+    };
+
+    let mut pixels = Vec::with_capacity(WIDTH * HEIGHT * 3);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            pixels.push(((x * 224 / WIDTH) + noise()) as u8);
+            pixels.push(((y * 224 / HEIGHT) + noise()) as u8);
+            pixels.push((((x + y) * 112 / WIDTH) + noise()) as u8);
+        }
+    }
+
+    pixels
+}
+
+fn extractor(histogram_bits: u8, color_average: ColorAverage, max_colors: u8) -> PaletteExtractor {
+    PaletteExtractor::builder()
+        .quality(Quality::new(1))
+        .max_colors(MaxColors::new(max_colors))
+        .pixel_filter(PixelFilter::None)
+        .histogram_bits(histogram_bits)
+        .color_average(color_average)
+        .build()
+        .unwrap()
+}
+
+/// The plain `u8` RGB path, with the default options and with every pixel sampled, as in the original API.
+fn default_options(c: &mut Criterion) {
+    let pixels = photo();
+    let mut group = c.benchmark_group("default");
+    group.sample_size(10);
+
+    group.bench_function("rgb", |b| b.iter(|| get_palette_rgb(black_box(pixels.as_slice()))));
+    group.bench_function("rgb_quality_1", |b| {
+        b.iter(|| {
+            get_palette_with_options(
+                black_box(pixels.as_slice()),
+                PixelEncoding::Rgb,
+                Quality::new(1),
+                MaxColors::new(10),
+                PixelFilter::default(),
+            )
+        })
+    });
+
+    group.finish();
+}
+
+fn extract(c: &mut Criterion) {
+    let pixels = photo();
+    let mut group = c.benchmark_group("extract");
+    group.sample_size(10);
+
+    for bits in [5, 6, 7, 8] {
+        let bin_center = extractor(bits, ColorAverage::BinCenter, 16);
+        group.bench_with_input(BenchmarkId::new("bin_center", bits), &bits, |b, _| {
+            b.iter(|| bin_center.extract(black_box(pixels.as_slice())).unwrap())
+        });
+
+        let pixel_mean = extractor(bits, ColorAverage::PixelMean, 16);
+        group.bench_with_input(BenchmarkId::new("pixel_mean", bits), &bits, |b, _| {
+            b.iter(|| pixel_mean.extract(black_box(pixels.as_slice())).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn max_colors(c: &mut Criterion) {
+    let pixels = photo();
+    let mut group = c.benchmark_group("max_colors");
+    group.sample_size(10);
+
+    for max_colors in [16, 64, 255] {
+        let extractor = extractor(6, ColorAverage::BinCenter, max_colors);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(max_colors),
+            &max_colors,
+            |b, _| b.iter(|| extractor.extract(black_box(pixels.as_slice())).unwrap()),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, default_options, extract, max_colors);
+criterion_main!(benches);
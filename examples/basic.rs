@@ -9,7 +9,7 @@ fn main() {
 
     let img_bytes = img_buffer.as_raw();
 
-    let color_palette = get_palette_rgb(&img_bytes);
+    let color_palette = get_palette_rgb(img_bytes);
 
     color_palette.iter().for_each(|x| println!("{:?}", x));
 }
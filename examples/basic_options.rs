@@ -10,11 +10,11 @@ fn main() {
     let img_bytes = img_buffer.as_raw();
 
     let color_palette = get_palette_with_options(
-        &img_bytes,
+        img_bytes,
         PixelEncoding::Rgb,
         Quality::new(1),
         MaxColors::new(4),
-        PixelFilter::White,
+        PixelFilter::White { threshold: 250 },
     );
 
     color_palette.iter().for_each(|x| println!("{:?}", x));
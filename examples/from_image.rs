@@ -1,4 +1,4 @@
-use palette_extract::{get_palette_with_options, MaxColors, PixelEncoding, PixelFilter, Quality};
+use palette_extract::{ImagePaletteExt, MaxColors, PaletteExtractor, PixelFilter, Quality};
 
 fn main() {
     
@@ -8,15 +8,16 @@ fn main() {
     let zebra = "./examples/test_images/zebra.jpg";
 
     let img = image::open(zebra).unwrap();
-    let pixels = img.as_bytes();
 
-    let res = get_palette_with_options(
-        &pixels,
-        PixelEncoding::Rgb,
-        Quality::new(1),
-        MaxColors::new(10),
-        PixelFilter::None,
-    );
+    // the encoding is picked from the color type of the decoded image
+    let extractor = PaletteExtractor::builder()
+        .quality(Quality::new(1))
+        .max_colors(MaxColors::new(10))
+        .pixel_filter(PixelFilter::None)
+        .build()
+        .unwrap();
+
+    let res = img.palette(&extractor).unwrap();
 
     res.iter().for_each(|x| println!("{:?}", x));
 }
@@ -0,0 +1,47 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use palette_extract::{
+    try_get_palette_with_options, MaxColors, PixelEncoding, PixelFilter, Quality,
+};
+
+// The first four bytes select the options, everything after them is treated as pixels.
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 4 {
+        return;
+    }
+
+    let (options, pixels) = data.split_at(4);
+
+    let encoding = match options[0] % 13 {
+        0 => PixelEncoding::Rgb,
+        1 => PixelEncoding::Rgba,
+        2 => PixelEncoding::Bgr,
+        3 => PixelEncoding::Bgra,
+        4 => PixelEncoding::Argb,
+        5 => PixelEncoding::Abgr,
+        6 => PixelEncoding::Gray,
+        7 => PixelEncoding::GrayAlpha,
+        8 => PixelEncoding::RgbaPremultiplied,
+        9 => PixelEncoding::BgraPremultiplied,
+        10 => PixelEncoding::ArgbPremultiplied,
+        11 => PixelEncoding::AbgrPremultiplied,
+        _ => PixelEncoding::GrayAlphaPremultiplied,
+    };
+
+    let pixel_filter = if options[0] & 0x80 == 0 {
+        PixelFilter::None
+    } else {
+        PixelFilter::White {
+            threshold: options[3],
+        }
+    };
+
+    let _ = try_get_palette_with_options(
+        pixels,
+        encoding,
+        Quality::new(options[1]),
+        MaxColors::new(options[2]),
+        pixel_filter,
+    );
+});
@@ -0,0 +1,192 @@
+//! Extracts color palettes from image files. Built with the `cli` feature.
+
+mod output;
+mod preview;
+
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+use palette_extract::{
+    ImagePaletteExt, MaxColors, PaletteError, PaletteExtractor, PixelEncoding, PixelFilter,
+    Quality, Swatch,
+};
+
+use output::{write_palettes, Format, Palette};
+use preview::{ColorChoice, ColorSupport};
+
+/// Extract color palettes from images.
+#[derive(Parser, Debug)]
+#[command(name = "palette-extract", version)]
+struct Args {
+    /// The images to extract palettes from.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// The max number of colors to extract from each image.
+    #[arg(short, long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(1..))]
+    max_colors: u8,
+
+    /// The sampling step. Higher is faster, but may be less accurate.
+    #[arg(short, long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(1..))]
+    quality: u8,
+
+    /// The pixels to discard: `none`, `white` or `black`, with a threshold like `white=240`.
+    #[arg(short, long, default_value = "white", value_parser = parse_filter)]
+    filter: PixelFilter,
+
+    /// Read the files as raw 8-bit pixels with this encoding, instead of decoding them as images.
+    #[arg(short, long, value_enum)]
+    encoding: Option<Encoding>,
+
+    /// How to print the palettes.
+    #[arg(long, value_enum, default_value_t = Format::Hex)]
+    format: Format,
+
+    /// When to preview colors in hex output. Uses 24-bit colors if `COLORTERM` is `truecolor` or `24bit`, and the 256 color palette otherwise.
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+}
+
+/// The pixel encodings of raw input files.
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum Encoding {
+    Rgb,
+    Rgba,
+    Bgr,
+    Bgra,
+    Argb,
+    Abgr,
+    Gray,
+    GrayAlpha,
+    RgbaPremultiplied,
+    BgraPremultiplied,
+    ArgbPremultiplied,
+    AbgrPremultiplied,
+    GrayAlphaPremultiplied,
+}
+
+impl From<Encoding> for PixelEncoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Rgb => PixelEncoding::Rgb,
+            Encoding::Rgba => PixelEncoding::Rgba,
+            Encoding::Bgr => PixelEncoding::Bgr,
+            Encoding::Bgra => PixelEncoding::Bgra,
+            Encoding::Argb => PixelEncoding::Argb,
+            Encoding::Abgr => PixelEncoding::Abgr,
+            Encoding::Gray => PixelEncoding::Gray,
+            Encoding::GrayAlpha => PixelEncoding::GrayAlpha,
+            Encoding::RgbaPremultiplied => PixelEncoding::RgbaPremultiplied,
+            Encoding::BgraPremultiplied => PixelEncoding::BgraPremultiplied,
+            Encoding::ArgbPremultiplied => PixelEncoding::ArgbPremultiplied,
+            Encoding::AbgrPremultiplied => PixelEncoding::AbgrPremultiplied,
+            Encoding::GrayAlphaPremultiplied => PixelEncoding::GrayAlphaPremultiplied,
+        }
+    }
+}
+
+/// The threshold of `--filter black` when none is given, mirroring the default white threshold.
+const DEFAULT_BLACK_THRESHOLD: u8 = 5;
+
+fn parse_filter(s: &str) -> Result<PixelFilter, String> {
+    let (name, threshold) = match s.split_once('=') {
+        Some((name, threshold)) => {
+            let threshold = threshold
+                .parse::<u8>()
+                .map_err(|_| format!("invalid threshold `{}`, expected 0-255", threshold))?;
+            (name, Some(threshold))
+        }
+        None => (s, None),
+    };
+
+    match (name, threshold) {
+        ("none", None) => Ok(PixelFilter::None),
+        ("white", threshold) => Ok(PixelFilter::White {
+            threshold: threshold.unwrap_or(250),
+        }),
+        ("black", threshold) => Ok(PixelFilter::Black {
+            threshold: threshold.unwrap_or(DEFAULT_BLACK_THRESHOLD),
+        }),
+        _ => Err(format!(
+            "invalid filter `{}`, expected `none`, `white[=N]` or `black[=N]`",
+            s
+        )),
+    }
+}
+
+/// Reads `path` and extracts its palette, decoding it as an image unless `encoding` is given.
+fn extract(
+    path: &PathBuf,
+    encoding: Option<Encoding>,
+    extractor: &PaletteExtractor,
+) -> Result<Vec<Swatch>, String> {
+    let swatches = match encoding {
+        Some(_) => {
+            let pixels = std::fs::read(path).map_err(|err| err.to_string())?;
+            extractor.extract_swatches(&pixels)
+        }
+        None => {
+            let img = image::open(path).map_err(|err| err.to_string())?;
+            img.swatches(extractor)
+        }
+    };
+
+    swatches.map_err(|err: PaletteError| err.to_string())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let extractor = PaletteExtractor::builder()
+        .encoding(args.encoding.map_or(PixelEncoding::Rgb, PixelEncoding::from))
+        .quality(Quality::new(args.quality))
+        .max_colors(MaxColors::new(args.max_colors))
+        .pixel_filter(args.filter.clone())
+        .build();
+    let extractor = match extractor {
+        Ok(extractor) => extractor,
+        Err(err) => {
+            eprintln!("palette-extract: {}", err);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut palettes = vec![];
+    let mut failed = false;
+
+    for path in &args.paths {
+        match extract(path, args.encoding, &extractor) {
+            Ok(swatches) => palettes.push(Palette {
+                path: path.display().to_string(),
+                swatches,
+            }),
+            Err(err) => {
+                eprintln!("palette-extract: {}: {}", path.display(), err);
+                failed = true;
+            }
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    let color_support = ColorSupport::detect(
+        args.color,
+        stdout.is_terminal(),
+        std::env::var("COLORTERM").ok().as_deref(),
+        std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+    );
+
+    let written = write_palettes(&mut stdout, args.format, color_support, &palettes)
+        .and_then(|_| stdout.flush());
+    if let Err(err) = written {
+        eprintln!("palette-extract: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use palette_extract::{Color, Swatch};
+
+use crate::preview::{swatch_block, ColorSupport};
+
+/// How palettes are printed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One hex color per line, under the path of each image when there's more than one. In a terminal, each color is previewed along with its population.
+    Hex,
+
+    /// An array with an object for each image.
+    Json,
+
+    /// A row for each color, with a header.
+    Csv,
+}
+
+/// The palette extracted from the image at `path`.
+pub struct Palette {
+    pub path: String,
+    pub swatches: Vec<Swatch>,
+}
+
+pub fn write_palettes(
+    out: &mut impl Write,
+    format: Format,
+    color_support: ColorSupport,
+    palettes: &[Palette],
+) -> io::Result<()> {
+    match format {
+        Format::Hex => write_hex(out, color_support, palettes),
+        Format::Json => write_json(out, palettes),
+        Format::Csv => write_csv(out, palettes),
+    }
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.r, color.g, color.b)
+}
+
+fn write_hex(
+    out: &mut impl Write,
+    color_support: ColorSupport,
+    palettes: &[Palette],
+) -> io::Result<()> {
+    let headers = palettes.len() > 1;
+
+    for (i, palette) in palettes.iter().enumerate() {
+        if headers {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "{}:", palette.path)?;
+        }
+
+        for swatch in &palette.swatches {
+            match color_support {
+                ColorSupport::None => writeln!(out, "{}", hex(swatch.color))?,
+                _ => writeln!(
+                    out,
+                    "{} {} {}",
+                    swatch_block(swatch.color, color_support),
+                    hex(swatch.color),
+                    swatch.population
+                )?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_json(out: &mut impl Write, palettes: &[Palette]) -> io::Result<()> {
+    writeln!(out, "[")?;
+
+    for (i, palette) in palettes.iter().enumerate() {
+        writeln!(out, "  {{")?;
+        writeln!(out, "    \"path\": {},", json_string(&palette.path))?;
+        writeln!(out, "    \"palette\": [")?;
+
+        for (j, swatch) in palette.swatches.iter().enumerate() {
+            let Color { r, g, b } = swatch.color;
+            write!(
+                out,
+                concat!(
+                    "      {{\"hex\": \"{}\", \"r\": {}, \"g\": {}, \"b\": {}, ",
+                    "\"population\": {}, \"proportion\": {}}}"
+                ),
+                hex(swatch.color),
+                r,
+                g,
+                b,
+                swatch.population,
+                swatch.proportion
+            )?;
+            writeln!(out, "{}", if j + 1 < palette.swatches.len() { "," } else { "" })?;
+        }
+
+        writeln!(out, "    ]")?;
+        writeln!(out, "  }}{}", if i + 1 < palettes.len() { "," } else { "" })?;
+    }
+
+    writeln!(out, "]")
+}
+
+fn write_csv(out: &mut impl Write, palettes: &[Palette]) -> io::Result<()> {
+    writeln!(out, "path,hex,r,g,b,population,proportion")?;
+
+    for palette in palettes {
+        for swatch in &palette.swatches {
+            let Color { r, g, b } = swatch.color;
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                csv_field(&palette.path),
+                hex(swatch.color),
+                r,
+                g,
+                b,
+                swatch.population,
+                swatch.proportion
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Quotes and escapes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// Quotes `s` as a CSV field, if it contains anything that would break the row.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
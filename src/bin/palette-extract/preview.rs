@@ -0,0 +1,92 @@
+use clap::ValueEnum;
+use palette_extract::Color;
+
+/// When to preview colors with ANSI escape codes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Only when printing to a terminal, and `NO_COLOR` isn't set.
+    Auto,
+
+    /// Always, even when the output is redirected.
+    Always,
+
+    /// Never.
+    Never,
+}
+
+/// The colors the terminal can show.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// No colors, only plain text.
+    None,
+
+    /// The 256 color xterm palette.
+    Ansi256,
+
+    /// 24-bit colors.
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Works out the colors to use from the choice on the command line, whether stdout is a terminal, and the `COLORTERM` and `NO_COLOR` environment variables.
+    pub fn detect(
+        choice: ColorChoice,
+        is_terminal: bool,
+        colorterm: Option<&str>,
+        no_color: bool,
+    ) -> ColorSupport {
+        let enabled = match choice {
+            ColorChoice::Auto => is_terminal && !no_color,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+
+        match colorterm {
+            _ if !enabled => ColorSupport::None,
+            Some("truecolor") | Some("24bit") => ColorSupport::TrueColor,
+            _ => ColorSupport::Ansi256,
+        }
+    }
+}
+
+/// Renders `color` as a block of background color, or nothing without color support.
+pub fn swatch_block(color: Color, support: ColorSupport) -> String {
+    match support {
+        ColorSupport::None => String::new(),
+        ColorSupport::TrueColor => {
+            format!("\x1b[48;2;{};{};{}m      \x1b[0m", color.r, color.g, color.b)
+        }
+        ColorSupport::Ansi256 => format!("\x1b[48;5;{}m      \x1b[0m", ansi256(color)),
+    }
+}
+
+/// The levels of each channel in the 6x6x6 color cube of the 256 color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Finds the closest color in the 256 color palette, from the color cube or the grayscale ramp.
+pub fn ansi256(color: Color) -> u8 {
+    let nearest_level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(v))
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (nearest_level(color.r), nearest_level(color.g), nearest_level(color.b));
+    let cube = Color::new(CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+    // the ramp goes from 8 to 238 in steps of 10
+    let average = (color.r as u32 + color.g as u32 + color.b as u32) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + step * 10;
+    let gray = Color::new(gray_level, gray_level, gray_level);
+
+    if distance(color, gray) < distance(color, cube) {
+        232 + step
+    } else {
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8
+    }
+}
+
+fn distance(a: Color, b: Color) -> u32 {
+    let d = |x: u8, y: u8| (x.abs_diff(y) as u32).pow(2);
+    d(a.r, b.r) + d(a.g, b.g) + d(a.b, b.b)
+}
@@ -0,0 +1,318 @@
+use std::ops::RangeInclusive;
+
+use crate::mmcq_impl::{extract_dominant_swatch, extract_swatches};
+use crate::{
+    AlphaPolicy, Color, ColorAverage, ExactColors, IntoImageView, MaxColors, PaletteError, PixelEncoding, PixelFilter, Quality, Swatch,
+    ToneMapping, TransferFunction,
+};
+
+/// Pixels with an alpha at or below this are discarded, unless set otherwise.
+const DEFAULT_ALPHA_THRESHOLD: u8 = 125;
+
+/// The number of bits each channel of the histogram is quantized to, unless set otherwise.
+const DEFAULT_HISTOGRAM_BITS: u8 = 5;
+
+/// The supported numbers of bits per channel of the histogram.
+const HISTOGRAM_BITS: RangeInclusive<u8> = 4..=8;
+
+/// A validated, reusable set of options for extracting color palettes.
+///
+/// Created with ['PaletteExtractor::builder'](PaletteExtractor::builder). Once built, an extractor can be cloned and shared across threads, and used to extract palettes from any number of images.
+///
+/// # Examples
+/// ```
+/// use palette_extract::{Color, MaxColors, PaletteExtractor, PixelEncoding, Quality};
+///
+/// let extractor = PaletteExtractor::builder()
+///     .encoding(PixelEncoding::Rgb)
+///     .quality(Quality::new(1))
+///     .max_colors(MaxColors::new(4))
+///     .build()
+///     .unwrap();
+///
+/// let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0];
+///
+/// let palette = extractor.extract(&pixels).unwrap();
+///
+/// assert_eq!(palette[0], Color::new(252, 4, 4));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PaletteExtractor {
+    pub(crate) encoding: PixelEncoding,
+    pub(crate) quality: u8,
+    pub(crate) max_colors: u8,
+    pub(crate) pixel_filter: PixelFilter,
+    pub(crate) alpha_threshold: u8,
+    pub(crate) alpha_policy: AlphaPolicy,
+    pub(crate) transfer_function: TransferFunction,
+    pub(crate) tone_mapping: ToneMapping,
+    pub(crate) histogram_bits: u8,
+    pub(crate) color_average: ColorAverage,
+    pub(crate) exact_colors: ExactColors,
+}
+
+impl PaletteExtractor {
+    /// Creates a new ['PaletteExtractorBuilder'](PaletteExtractorBuilder), starting from the default options.
+    pub fn builder() -> PaletteExtractorBuilder {
+        PaletteExtractorBuilder::default()
+    }
+
+    /// Extracts a color palette from a slice of pixels, represented with this extractor's ['PixelEncoding'](PixelEncoding).
+    ///
+    /// `pixels` can be a slice of `u8`, `u16` or `f32` samples, any other ['PixelData'](crate::PixelData), or an ['ImageView'](crate::ImageView) of a padded image, which is read with its own encoding.
+    ///
+    /// As in Color Thief, the palette can end with colors that no pixel falls into, when the median cut leaves some boxes empty, e.g. for images with fewer colors than the max number of colors. They're the midpoints of the empty boxes, and have a population of 0 in ['extract_swatches'](PaletteExtractor::extract_swatches).
+    ///
+    /// # Errors
+    /// - [`PaletteError::EmptyInput`] if `pixels` is empty.
+    /// - [`PaletteError::InvalidBufferLength`] if the length of `pixels` is not a multiple of the size of a pixel.
+    /// - [`PaletteError::NoPixels`] if every pixel is transparent or removed by the pixel filter.
+    /// - [`PaletteError::UncuttableBox`] if an iteration of the algorithm can't be performed.
+    pub fn extract<'a>(
+        &self,
+        pixels: impl IntoImageView<'a>,
+    ) -> Result<Vec<Color>, PaletteError> {
+        let swatches = self.extract_swatches(pixels)?;
+
+        Ok(swatches.iter().map(|s| s.color).collect())
+    }
+
+    /// Extracts a color palette from a slice of pixels as ['Swatch'](Swatch)es, which also describe how much of the image each color represents.
+    ///
+    /// The swatches are in the same order as the colors returned by ['extract'](PaletteExtractor::extract).
+    ///
+    /// # Examples
+    /// ```
+    /// use palette_extract::{Color, PaletteExtractor, PixelFilter, Quality};
+    ///
+    /// let extractor = PaletteExtractor::builder()
+    ///     .quality(Quality::new(1))
+    ///     .pixel_filter(PixelFilter::None)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 0, 255];
+    ///
+    /// let swatches = extractor.extract_swatches(&pixels).unwrap();
+    ///
+    /// let red = swatches.iter().find(|s| s.color == Color::new(252, 4, 4)).unwrap();
+    /// assert_eq!(red.population, 3);
+    /// assert_eq!(red.proportion, 0.75);
+    /// ```
+    ///
+    /// # Errors
+    /// See ['extract'](PaletteExtractor::extract).
+    pub fn extract_swatches<'a>(
+        &self,
+        pixels: impl IntoImageView<'a>,
+    ) -> Result<Vec<Swatch>, PaletteError> {
+        extract_swatches(&pixels.into_image_view(self.encoding)?, self)
+    }
+
+    /// Finds the most common color in a slice of pixels, as the average color of its most populous cluster.
+    ///
+    /// The median cut starts by cutting the most populous box of the color space in two, until there are 75% of the max number of colors. The dominant color is the most populous of those boxes, so a shade spread over a few neighbouring cells of the color histogram, like a gradient, counts as a single color. Unlike the first color returned by ['extract'](PaletteExtractor::extract), which is ordered by population and volume, it doesn't depend on the second phase of the median cut, which cuts boxes by volume and is skipped. The more colors, the smaller the clusters.
+    ///
+    /// With ['ExactColors'](ExactColors), images with few enough colors return their most populous exact color instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use palette_extract::{Color, PaletteExtractor, Quality};
+    ///
+    /// let extractor = PaletteExtractor::builder()
+    ///     .quality(Quality::new(1))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let pixels: [u8; 12] = [0, 0, 255, 255, 0, 0, 255, 0, 0, 255, 0, 0];
+    ///
+    /// assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(252, 4, 4));
+    /// ```
+    ///
+    /// # Errors
+    /// See ['extract'](PaletteExtractor::extract).
+    pub fn dominant_color<'a>(
+        &self,
+        pixels: impl IntoImageView<'a>,
+    ) -> Result<Color, PaletteError> {
+        Ok(self.dominant_swatch(pixels)?.color)
+    }
+
+    /// Finds the most common color in a slice of pixels, along with its population and bounds, as a ['Swatch'](Swatch). See ['dominant_color'](PaletteExtractor::dominant_color).
+    ///
+    /// # Errors
+    /// See ['dominant_color'](PaletteExtractor::dominant_color).
+    pub fn dominant_swatch<'a>(
+        &self,
+        pixels: impl IntoImageView<'a>,
+    ) -> Result<Swatch, PaletteError> {
+        extract_dominant_swatch(&pixels.into_image_view(self.encoding)?, self)
+    }
+}
+
+impl Default for PaletteExtractor {
+    fn default() -> Self {
+        PaletteExtractor {
+            encoding: PixelEncoding::Rgb,
+            quality: Quality::default().0,
+            max_colors: MaxColors::default().0,
+            pixel_filter: PixelFilter::default(),
+            alpha_threshold: DEFAULT_ALPHA_THRESHOLD,
+            alpha_policy: AlphaPolicy::default(),
+            transfer_function: TransferFunction::default(),
+            tone_mapping: ToneMapping::default(),
+            histogram_bits: DEFAULT_HISTOGRAM_BITS,
+            color_average: ColorAverage::default(),
+            exact_colors: ExactColors::default(),
+        }
+    }
+}
+
+/// Builder for a ['PaletteExtractor'](PaletteExtractor).
+///
+/// Defaults to ['PixelEncoding::Rgb'](PixelEncoding::Rgb) and the defaults of ['Quality'](Quality), ['MaxColors'](MaxColors) and ['PixelFilter'](PixelFilter).
+#[derive(Clone, Debug)]
+pub struct PaletteExtractorBuilder {
+    encoding: PixelEncoding,
+    quality: Quality,
+    max_colors: MaxColors,
+    pixel_filter: PixelFilter,
+    alpha_threshold: u8,
+    alpha_policy: AlphaPolicy,
+    transfer_function: TransferFunction,
+    tone_mapping: ToneMapping,
+    histogram_bits: u8,
+    color_average: ColorAverage,
+    exact_colors: ExactColors,
+}
+
+impl PaletteExtractorBuilder {
+    /// Sets how pixels are represented in the slices passed to the extractor.
+    pub fn encoding(mut self, encoding: PixelEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the quality, i.e. the sampling step, used when reading pixels.
+    pub fn quality(mut self, quality: Quality) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Sets the max number of colors to extract.
+    pub fn max_colors(mut self, max_colors: MaxColors) -> Self {
+        self.max_colors = max_colors;
+        self
+    }
+
+    /// Sets the filter used to exclude pixels from the palette.
+    pub fn pixel_filter(mut self, pixel_filter: PixelFilter) -> Self {
+        self.pixel_filter = pixel_filter;
+        self
+    }
+
+    /// Sets the alpha a pixel must exceed to be sampled, from 0 to 254. Pixels at or below it are discarded as transparent. Defaults to 125.
+    ///
+    /// Lower thresholds keep more of the soft, anti-aliased edges of logos and icons. Fully transparent pixels are always discarded. Only used with ['AlphaPolicy::Discard'](AlphaPolicy::Discard). A threshold of 255 is rejected by ['build'](PaletteExtractorBuilder::build), since no pixel could exceed it, not even those of images without an alpha channel.
+    pub fn alpha_threshold(mut self, alpha_threshold: u8) -> Self {
+        self.alpha_threshold = alpha_threshold;
+        self
+    }
+
+    /// Sets how translucent pixels contribute to the palette.
+    pub fn alpha_policy(mut self, alpha_policy: AlphaPolicy) -> Self {
+        self.alpha_policy = alpha_policy;
+        self
+    }
+
+    /// Sets how the color channels of floating point pixels are encoded. Only used for ['PixelData::F32'](crate::PixelData::F32).
+    pub fn transfer_function(mut self, transfer_function: TransferFunction) -> Self {
+        self.transfer_function = transfer_function;
+        self
+    }
+
+    /// Sets how floating point colors brighter than `1.0` are handled. Only used for ['PixelData::F32'](crate::PixelData::F32).
+    pub fn tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+        self.tone_mapping = tone_mapping;
+        self
+    }
+
+    /// Sets the number of bits, from 4 to 8, that each channel is quantized to in the color histogram. Defaults to 5.
+    ///
+    /// More bits tell apart colors that are closer together, at the cost of memory and speed: the histogram has `2^(3 * bits)` cells, each with an 8-byte count, so 8 bits takes about 130 MiB while 5 bits takes under 1 MiB. ['ColorAverage::PixelMean'](ColorAverage::PixelMean) takes 4 times as much. With 8 bits, images with few colors get their exact colors back.
+    pub fn histogram_bits(mut self, histogram_bits: u8) -> Self {
+        self.histogram_bits = histogram_bits;
+        self
+    }
+
+    /// Sets how the color of each swatch is worked out from the pixels it covers.
+    pub fn color_average(mut self, color_average: ColorAverage) -> Self {
+        self.color_average = color_average;
+        self
+    }
+
+    /// Sets when the exact colors of an image are returned, skipping the median cut.
+    pub fn exact_colors(mut self, exact_colors: ExactColors) -> Self {
+        self.exact_colors = exact_colors;
+        self
+    }
+
+    /// Validates the options and creates the ['PaletteExtractor'](PaletteExtractor).
+    ///
+    /// # Errors
+    /// - [`PaletteError::ZeroQuality`] if the quality is 0.
+    /// - [`PaletteError::ZeroMaxColors`] if the max number of colors is 0.
+    /// - [`PaletteError::InvalidHistogramBits`] if the histogram bits are outside of 4 to 8.
+    /// - [`PaletteError::InvalidAlphaThreshold`] if the alpha threshold is 255.
+    pub fn build(self) -> Result<PaletteExtractor, PaletteError> {
+        if self.quality.0 == 0 {
+            return Err(PaletteError::ZeroQuality);
+        }
+
+        if self.max_colors.0 == 0 {
+            return Err(PaletteError::ZeroMaxColors);
+        }
+
+        if !HISTOGRAM_BITS.contains(&self.histogram_bits) {
+            return Err(PaletteError::InvalidHistogramBits {
+                bits: self.histogram_bits,
+            });
+        }
+
+        if self.alpha_threshold == u8::MAX {
+            return Err(PaletteError::InvalidAlphaThreshold);
+        }
+
+        Ok(PaletteExtractor {
+            encoding: self.encoding,
+            quality: self.quality.0,
+            max_colors: self.max_colors.0,
+            pixel_filter: self.pixel_filter,
+            alpha_threshold: self.alpha_threshold,
+            alpha_policy: self.alpha_policy,
+            transfer_function: self.transfer_function,
+            tone_mapping: self.tone_mapping,
+            histogram_bits: self.histogram_bits,
+            color_average: self.color_average,
+            exact_colors: self.exact_colors,
+        })
+    }
+}
+
+impl Default for PaletteExtractorBuilder {
+    fn default() -> Self {
+        PaletteExtractorBuilder {
+            encoding: PixelEncoding::Rgb,
+            quality: Quality::default(),
+            max_colors: MaxColors::default(),
+            pixel_filter: PixelFilter::default(),
+            alpha_threshold: DEFAULT_ALPHA_THRESHOLD,
+            alpha_policy: AlphaPolicy::default(),
+            transfer_function: TransferFunction::default(),
+            tone_mapping: ToneMapping::default(),
+            histogram_bits: DEFAULT_HISTOGRAM_BITS,
+            color_average: ColorAverage::default(),
+            exact_colors: ExactColors::default(),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::ops::Deref;
+
+use ::image::{DynamicImage, ImageBuffer, Luma, LumaA, Pixel, Rgb, Rgba};
+
+use crate::{
+    Color, ImageView, IntoImageView, PaletteError, PaletteExtractor, PixelEncoding, Swatch,
+    TransferFunction,
+};
+
+/// Extracts color palettes from images of the `image` crate. Requires the `image` feature.
+///
+/// The pixel encoding is picked from the color type of the image, and the extractor's encoding is ignored. Images with 8 or 16 bits per channel, or floating point channels, are read without copying. Other images are converted to 8-bit RGBA first.
+///
+/// Floating point images of the `image` crate hold the same sRGB encoded values as its integer images, so they're always read with ['TransferFunction::Srgb'](TransferFunction::Srgb), whatever the extractor's transfer function. The 8-bit, 16-bit and floating point versions of an image give the same palette.
+///
+/// `ImageBuffer`s of those color types can also be passed to a ['PaletteExtractor'](PaletteExtractor) directly.
+///
+/// # Examples
+/// ```
+/// use palette_extract::{Color, ImagePaletteExt, PaletteExtractor};
+///
+/// let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+///     4,
+///     4,
+///     image::Rgba([255, 0, 0, 255]),
+/// ));
+///
+/// let extractor = PaletteExtractor::default();
+///
+/// assert_eq!(img.dominant_color(&extractor).unwrap(), Color::new(252, 4, 4));
+/// ```
+pub trait ImagePaletteExt {
+    /// Extracts a color palette from the image. See ['PaletteExtractor::extract'](PaletteExtractor::extract).
+    ///
+    /// # Errors
+    /// See ['PaletteExtractor::extract'](PaletteExtractor::extract).
+    fn palette(&self, extractor: &PaletteExtractor) -> Result<Vec<Color>, PaletteError>;
+
+    /// Extracts a color palette from the image as ['Swatch'](Swatch)es. See ['PaletteExtractor::extract_swatches'](PaletteExtractor::extract_swatches).
+    ///
+    /// # Errors
+    /// See ['PaletteExtractor::extract'](PaletteExtractor::extract).
+    fn swatches(&self, extractor: &PaletteExtractor) -> Result<Vec<Swatch>, PaletteError>;
+
+    /// Finds the most common color in the image. See ['PaletteExtractor::dominant_color'](PaletteExtractor::dominant_color).
+    ///
+    /// # Errors
+    /// See ['PaletteExtractor::dominant_color'](PaletteExtractor::dominant_color).
+    fn dominant_color(&self, extractor: &PaletteExtractor) -> Result<Color, PaletteError>;
+}
+
+impl ImagePaletteExt for DynamicImage {
+    fn palette(&self, extractor: &PaletteExtractor) -> Result<Vec<Color>, PaletteError> {
+        with_view(self, |view| extractor.extract(view))
+    }
+
+    fn swatches(&self, extractor: &PaletteExtractor) -> Result<Vec<Swatch>, PaletteError> {
+        with_view(self, |view| extractor.extract_swatches(view))
+    }
+
+    fn dominant_color(&self, extractor: &PaletteExtractor) -> Result<Color, PaletteError> {
+        with_view(self, |view| extractor.dominant_color(view))
+    }
+}
+
+/// Calls `f` with a view of `image`, converting it to 8-bit RGBA if it can't be read as it is.
+fn with_view<T>(
+    image: &DynamicImage,
+    f: impl FnOnce(ImageView) -> Result<T, PaletteError>,
+) -> Result<T, PaletteError> {
+    let view = match image {
+        DynamicImage::ImageLuma8(buffer) => buffer.into_image_view(PixelEncoding::Gray),
+        DynamicImage::ImageLumaA8(buffer) => buffer.into_image_view(PixelEncoding::GrayAlpha),
+        DynamicImage::ImageRgb8(buffer) => buffer.into_image_view(PixelEncoding::Rgb),
+        DynamicImage::ImageRgba8(buffer) => buffer.into_image_view(PixelEncoding::Rgba),
+        DynamicImage::ImageLuma16(buffer) => buffer.into_image_view(PixelEncoding::Gray),
+        DynamicImage::ImageLumaA16(buffer) => buffer.into_image_view(PixelEncoding::GrayAlpha),
+        DynamicImage::ImageRgb16(buffer) => buffer.into_image_view(PixelEncoding::Rgb),
+        DynamicImage::ImageRgba16(buffer) => buffer.into_image_view(PixelEncoding::Rgba),
+        DynamicImage::ImageRgb32F(buffer) => buffer.into_image_view(PixelEncoding::Rgb),
+        DynamicImage::ImageRgba32F(buffer) => buffer.into_image_view(PixelEncoding::Rgba),
+        _ => return f(image.to_rgba8().into_image_view(PixelEncoding::Rgba)?),
+    };
+
+    f(view?)
+}
+
+/// Implements zero-copy conversions for `ImageBuffer`s of a color type, read with an encoding.
+///
+/// Floating point buffers are sRGB encoded, like integer buffers.
+macro_rules! image_buffer {
+    ($($pixel:ty => $encoding:expr),* $(,)?) => {$(
+        impl<'a, C> IntoImageView<'a> for &'a ImageBuffer<$pixel, C>
+        where
+            C: Deref<Target = [<$pixel as Pixel>::Subpixel]>,
+        {
+            fn into_image_view(
+                self,
+                _encoding: PixelEncoding,
+            ) -> Result<ImageView<'a>, PaletteError> {
+                let samples: &'a [<$pixel as Pixel>::Subpixel] = self;
+
+                let view = ImageView::new(
+                    samples,
+                    $encoding,
+                    self.width() as usize,
+                    self.height() as usize,
+                )?;
+
+                Ok(view.with_transfer_function(TransferFunction::Srgb))
+            }
+        }
+
+        impl<C> ImagePaletteExt for ImageBuffer<$pixel, C>
+        where
+            C: Deref<Target = [<$pixel as Pixel>::Subpixel]>,
+        {
+            fn palette(&self, extractor: &PaletteExtractor) -> Result<Vec<Color>, PaletteError> {
+                extractor.extract(self)
+            }
+
+            fn swatches(
+                &self,
+                extractor: &PaletteExtractor,
+            ) -> Result<Vec<Swatch>, PaletteError> {
+                extractor.extract_swatches(self)
+            }
+
+            fn dominant_color(&self, extractor: &PaletteExtractor) -> Result<Color, PaletteError> {
+                extractor.dominant_color(self)
+            }
+        }
+    )*};
+}
+
+image_buffer! {
+    Luma<u8> => PixelEncoding::Gray,
+    LumaA<u8> => PixelEncoding::GrayAlpha,
+    Rgb<u8> => PixelEncoding::Rgb,
+    Rgba<u8> => PixelEncoding::Rgba,
+    Luma<u16> => PixelEncoding::Gray,
+    LumaA<u16> => PixelEncoding::GrayAlpha,
+    Rgb<u16> => PixelEncoding::Rgb,
+    Rgba<u16> => PixelEncoding::Rgba,
+    Luma<f32> => PixelEncoding::Gray,
+    LumaA<f32> => PixelEncoding::GrayAlpha,
+    Rgb<f32> => PixelEncoding::Rgb,
+    Rgba<f32> => PixelEncoding::Rgba,
+}
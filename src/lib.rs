@@ -1,7 +1,7 @@
 #![warn(missing_docs)]
 //! A lib crate for extracting a color palette from an image represented as a `u8` slice.
 //! 
-//! Supports `RGB` and `RGBA`.
+//! Supports `RGB`, `RGBA`, `BGR`, `BGRA`, `ARGB`, `ABGR`, grayscale and grayscale with alpha pixel encodings, with straight or premultiplied alpha, and with 8 or 16 bits per channel, or floating point channels.
 //! 
 //! # Examples
 //! See `examples` directory in code repo for a full set of functioning examples!
@@ -25,16 +25,70 @@
 //!     PixelEncoding::Rgb,
 //!     Quality::new(1),
 //!     MaxColors::new(4),
-//!     PixelFilter::White);
+//!     PixelFilter::White { threshold: 250 });
 //! 
 //! ```
+//!
+//! ## Without panicking
+//!
+//! ```
+//! use palette_extract::{try_get_palette_rgb, PaletteError};
+//!
+//! let pixels: [u8; 4] = [255, 0, 0, 255];
+//!
+//! let result = try_get_palette_rgb(&pixels);
+//!
+//! assert_eq!(result, Err(PaletteError::InvalidBufferLength { len: 4, stride: 3 }));
+//! ```
+//!
+//! ## Reusable extractor
+//!
+//! ```
+//! use palette_extract::{MaxColors, PaletteExtractor, PixelEncoding, PixelFilter};
+//!
+//! let extractor = PaletteExtractor::builder()
+//!     .encoding(PixelEncoding::Rgba)
+//!     .max_colors(MaxColors::new(4))
+//!     .pixel_filter(PixelFilter::None)
+//!     .build()
+//!     .unwrap();
+//!
+//! let pixels: [u8; 16] = [255, 0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255];
+//!
+//! let palette = extractor.extract(&pixels).unwrap();
+//! ```
+//!
+//! ## Images from the `image` crate
+//!
+//! With the optional `image` feature, `DynamicImage`s and `ImageBuffer`s can be used directly through the `ImagePaletteExt` trait, with the pixel encoding picked from their color type:
+//!
+//! ```ignore
+//! use palette_extract::{ImagePaletteExt, PaletteExtractor};
+//!
+//! let img = image::open("image.jpg").unwrap();
+//!
+//! let palette = img.palette(&PaletteExtractor::default()).unwrap();
+//! ```
 
+mod extractor;
+#[cfg(feature = "image")]
+mod image_ext;
 mod mmcq_impl;
 
-use mmcq_impl::extract_colors;
-pub use mmcq_impl::{Color, PixelEncoding};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+pub use extractor::{PaletteExtractor, PaletteExtractorBuilder};
+#[cfg(feature = "image")]
+pub use image_ext::ImagePaletteExt;
+pub use mmcq_impl::{
+    ByteOrder, Color, ColorBox, ImageView, IntoImageView, PaletteError, Pixel, PixelData,
+    PixelEncoding, PixelWeights, Rect, Swatch, ToneMapping, TransferFunction,
+};
 
 /// Represents the quality level used to extract the color palette. Defaults to 5.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Quality(u8);
 
 impl Quality {
@@ -59,6 +113,7 @@ impl Default for Quality {
 }
 
 /// Represents the max number of colors to extract from the image. Defaults to 10.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct MaxColors(u8);
 
 impl MaxColors {
@@ -83,18 +138,211 @@ impl Default for MaxColors {
 }
 
 /// Represents a filter that can be applied to algorithm to filter out particular pixels.
-#[derive(Eq, PartialEq)]
+#[derive(Clone)]
 pub enum PixelFilter {
     /// Represents no filter. I.E. all colors/pixels will be considered.
     None,
 
-    /// Represents a white pixel filter. All white pixels will be discarded for the purpose of extracting the palette from the image.
-    White,
+    /// Represents a white pixel filter. All white pixels, i.e. pixels whose red, green and blue channels are all above `threshold`, will be discarded for the purpose of extracting the palette from the image.
+    ///
+    /// The default filter uses a threshold of 250. Lower thresholds also discard off-white colors, like scanned paper.
+    White {
+        /// The value all color channels of a pixel must exceed for it to be discarded.
+        threshold: u8,
+    },
+
+    /// Represents a black pixel filter. All black pixels, i.e. pixels whose red, green and blue channels are all below `threshold`, will be discarded, e.g. to ignore letterboxing.
+    Black {
+        /// The value all color channels of a pixel must be below for it to be discarded.
+        threshold: u8,
+    },
+
+    /// Represents a user-defined filter. Only pixels the predicate keeps will be considered. Pixels discarded by the ['AlphaPolicy'](AlphaPolicy) are never passed to the predicate.
+    ///
+    /// Closures can be wrapped with ['PixelFilter::custom'](PixelFilter::custom). Two custom filters are equal only if they share the same predicate.
+    Custom(Arc<dyn PixelPredicate>),
+}
+
+impl PixelFilter {
+    /// Creates a ['PixelFilter::Custom'](PixelFilter::Custom) from a closure, which returns `true` for pixels to keep.
+    ///
+    /// # Examples
+    /// ```
+    /// use palette_extract::{Color, PaletteExtractor, PixelFilter, Quality};
+    ///
+    /// // discard chroma-key green
+    /// let filter = PixelFilter::custom(|p| !(p.g > 200 && p.r < 50 && p.b < 50));
+    ///
+    /// let extractor = PaletteExtractor::builder()
+    ///     .quality(Quality::new(1))
+    ///     .pixel_filter(filter)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let pixels: [u8; 12] = [0, 255, 0, 0, 255, 0, 0, 255, 0, 255, 0, 0];
+    ///
+    /// assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(252, 4, 4));
+    /// ```
+    pub fn custom<F>(predicate: F) -> PixelFilter
+    where
+        F: Fn(Pixel) -> bool + Send + Sync + 'static,
+    {
+        PixelFilter::Custom(Arc::new(predicate))
+    }
+
+    /// The range the red, green and blue channels of a pixel must all be in for the filter to discard it, or `None` if it doesn't discard pixels by their channels alone.
+    pub(crate) fn discarded_channels(&self) -> Option<RangeInclusive<u8>> {
+        match self {
+            PixelFilter::White { threshold } => Some(threshold.checked_add(1)?..=u8::MAX),
+            PixelFilter::Black { threshold } => Some(0..=threshold.checked_sub(1)?),
+            PixelFilter::None | PixelFilter::Custom(_) => None,
+        }
+    }
+
+    /// The predicate of a ['PixelFilter::Custom'](PixelFilter::Custom).
+    pub(crate) fn predicate(&self) -> Option<&dyn PixelPredicate> {
+        match self {
+            PixelFilter::Custom(predicate) => Some(predicate.as_ref()),
+            _ => None,
+        }
+    }
 }
 
 impl Default for PixelFilter {
     fn default() -> Self {
-        PixelFilter::White
+        PixelFilter::White { threshold: 250 }
+    }
+}
+
+impl PartialEq for PixelFilter {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (PixelFilter::None, PixelFilter::None) => true,
+            (PixelFilter::White { threshold: a }, PixelFilter::White { threshold: b }) => a == b,
+            (PixelFilter::Black { threshold: a }, PixelFilter::Black { threshold: b }) => a == b,
+            (PixelFilter::Custom(a), PixelFilter::Custom(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for PixelFilter {}
+
+impl fmt::Debug for PixelFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PixelFilter::None => write!(f, "None"),
+            PixelFilter::White { threshold } => {
+                f.debug_struct("White").field("threshold", threshold).finish()
+            }
+            PixelFilter::Black { threshold } => {
+                f.debug_struct("Black").field("threshold", threshold).finish()
+            }
+            PixelFilter::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// Represents how translucent pixels contribute to the palette. Defaults to ['AlphaPolicy::Discard'](AlphaPolicy::Discard).
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum AlphaPolicy {
+    /// Pixels with an alpha at or below the extractor's alpha threshold are discarded, and the rest count as fully opaque.
+    #[default]
+    Discard,
+
+    /// Every pixel counts in proportion to its alpha, so a pixel with an alpha of 51 counts as a fifth of an opaque one. Only fully transparent pixels are discarded, and the alpha threshold is ignored.
+    Weight,
+
+    /// Every pixel is alpha-composited over the given background color before it's binned, so fully transparent pixels become the background. Pixel filters see the composited pixels, and the alpha threshold is ignored.
+    Composite(Color),
+}
+
+impl AlphaPolicy {
+    /// The weight of an opaque pixel in the histogram.
+    pub(crate) fn unit(&self) -> u64 {
+        match self {
+            AlphaPolicy::Weight => u8::MAX.into(),
+            AlphaPolicy::Discard | AlphaPolicy::Composite(_) => 1,
+        }
+    }
+
+    /// Applies the policy to `pixel`, returning the pixel to bin and its weight in multiples of ['unit'](AlphaPolicy::unit), or `None` if it's discarded.
+    pub(crate) fn apply(&self, pixel: Pixel, alpha_threshold: u8) -> Option<(Pixel, u64)> {
+        match self {
+            AlphaPolicy::Discard if pixel.is_opaque(alpha_threshold) => Some((pixel, 1)),
+            AlphaPolicy::Discard => None,
+            AlphaPolicy::Weight if pixel.a > 0 => Some((pixel, pixel.a.into())),
+            AlphaPolicy::Weight => None,
+            AlphaPolicy::Composite(background) => Some((pixel.composite_over(*background), 1)),
+        }
+    }
+}
+
+/// Represents how the color of each swatch is worked out from the pixels it covers. Defaults to ['ColorAverage::BinCenter'](ColorAverage::BinCenter).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ColorAverage {
+    /// The centers of the histogram cells in the box are averaged, weighted by their counts. Colors are only as precise as the cells, but match the output of Color Thief.
+    #[default]
+    BinCenter,
+
+    /// The actual pixels in the box are averaged, so exact colors like a logo's `#FF0000` come back unchanged. The histogram also keeps the sums of the channels of every cell, which takes 3 times the memory of the counts.
+    PixelMean,
+}
+
+/// Represents when the exact colors of an image are returned, instead of the colors found by the median cut. Defaults to ['ExactColors::Never'](ExactColors::Never).
+///
+/// Logos, icons, flat illustrations and screenshots often have fewer colors than the max number of colors, and the median cut only approximates them. Exact colors come back unchanged, most populous first, with bounds covering only the color itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ExactColors {
+    /// The median cut always runs.
+    #[default]
+    Never,
+
+    /// The exact colors are returned when the sampled pixels have no more distinct colors than the max number of colors. Otherwise, the median cut runs.
+    Auto,
+
+    /// The most populous exact colors are always returned, up to the max number of colors. Every distinct color is counted, which can take a lot of memory for photos.
+    Always,
+}
+
+/// Decides which pixels are considered when extracting a palette. Used by ['PixelFilter::Custom'](PixelFilter::Custom).
+///
+/// Implemented for closures taking a ['Pixel'](Pixel) and returning a `bool`. Predicates must be `Send` and `Sync`, so extractors can be shared across threads.
+///
+/// # Examples
+/// ```
+/// use std::sync::Arc;
+/// use palette_extract::{Color, PaletteExtractor, Pixel, PixelFilter, PixelPredicate, Quality};
+///
+/// /// Discards pixels close to any of the brand colors.
+/// struct BrandColors(Vec<Color>);
+///
+/// impl PixelPredicate for BrandColors {
+///     fn keep(&self, p: Pixel) -> bool {
+///         !self.0.iter().any(|c| {
+///             c.r.abs_diff(p.r) < 8 && c.g.abs_diff(p.g) < 8 && c.b.abs_diff(p.b) < 8
+///         })
+///     }
+/// }
+///
+/// let extractor = PaletteExtractor::builder()
+///     .quality(Quality::new(1))
+///     .pixel_filter(PixelFilter::Custom(Arc::new(BrandColors(vec![Color::new(0, 0, 255)]))))
+///     .build()
+///     .unwrap();
+///
+/// let pixels: [u8; 12] = [0, 0, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0];
+///
+/// assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(252, 4, 4));
+/// ```
+pub trait PixelPredicate: Send + Sync {
+    /// Returns `true` if `pixel` should be considered, or `false` to discard it.
+    fn keep(&self, pixel: Pixel) -> bool;
+}
+
+impl<F: Fn(Pixel) -> bool + Send + Sync> PixelPredicate for F {
+    fn keep(&self, pixel: Pixel) -> bool {
+        self(pixel)
     }
 }
 
@@ -117,12 +365,12 @@ impl Default for PixelFilter {
 ///     PixelEncoding::Rgb,
 ///     Quality::new(1),
 ///     MaxColors::new(4),
-///     PixelFilter::White);
+///     PixelFilter::White { threshold: 250 });
 /// 
 /// ```
 /// 
 /// # Panics
-/// Panics if we are unable to perform an iteration of the algorithm.
+/// Panics if the palette can't be extracted. See ['try_get_palette_with_options'](try_get_palette_with_options) for the conditions under which this happens.
 ///
 pub fn get_palette_with_options(
     pixels: &[u8],
@@ -131,15 +379,51 @@ pub fn get_palette_with_options(
     max_colors: MaxColors,
     pixel_filter: PixelFilter,
 ) -> Vec<Color> {
-    let result = extract_colors(
-        pixels,
-        encoding,
-        quality.0,
-        max_colors.0,
-        pixel_filter == PixelFilter::White,
-    );
+    try_get_palette_with_options(pixels, encoding, quality, max_colors, pixel_filter)
+        .unwrap_or_else(|err| panic!("{}", err))
+}
 
-    result
+/// Extracts a color palette from a slice of RGB color bytes represented with `u8`, returning an error instead of panicking.
+///
+/// Takes the same arguments as ['get_palette_with_options'](get_palette_with_options). To extract palettes from many images with the same options, see ['PaletteExtractor'](PaletteExtractor).
+///
+/// # Examples
+/// ```
+/// use palette_extract::{try_get_palette_with_options, Quality, MaxColors, PaletteError, PixelEncoding, PixelFilter};
+///
+/// let pixels: [u8; 12] = [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+///
+/// let result = try_get_palette_with_options(&pixels,
+///     PixelEncoding::Rgb,
+///     Quality::new(1),
+///     MaxColors::new(4),
+///     PixelFilter::White { threshold: 250 });
+///
+/// assert_eq!(result, Err(PaletteError::NoPixels));
+/// ```
+///
+/// # Errors
+/// - [`PaletteError::ZeroQuality`] if `quality` is 0.
+/// - [`PaletteError::ZeroMaxColors`] if `max_colors` is 0.
+/// - [`PaletteError::EmptyInput`] if `pixels` is empty.
+/// - [`PaletteError::InvalidBufferLength`] if the length of `pixels` is not a multiple of ['PixelEncoding::stride'](PixelEncoding::stride).
+/// - [`PaletteError::NoPixels`] if every pixel is transparent or removed by `pixel_filter`.
+/// - [`PaletteError::UncuttableBox`] if an iteration of the algorithm can't be performed.
+///
+pub fn try_get_palette_with_options(
+    pixels: &[u8],
+    encoding: PixelEncoding,
+    quality: Quality,
+    max_colors: MaxColors,
+    pixel_filter: PixelFilter,
+) -> Result<Vec<Color>, PaletteError> {
+    PaletteExtractor::builder()
+        .encoding(encoding)
+        .quality(quality)
+        .max_colors(max_colors)
+        .pixel_filter(pixel_filter)
+        .build()?
+        .extract(pixels)
 }
 
 /// Extracts a color palette from a slice of RGB color bytes represented with `u8`. Uses ['Quality'](Quality) of 5, ['MaxColors'](MaxColors) of 10, and ['PixelFilter::None'](PixelFilter::None).
@@ -158,10 +442,31 @@ pub fn get_palette_with_options(
 /// let palette = get_palette_rgb(&pixels);
 /// ```
 /// # Panics
-/// Panics if we are unable to perform an iteration of the algorithm.
+/// Panics if the palette can't be extracted. See ['try_get_palette_rgb'](try_get_palette_rgb) for the conditions under which this happens.
 ///
 pub fn get_palette_rgb(pixels: &[u8]) -> Vec<Color> {
-    get_palette_with_options(
+    try_get_palette_rgb(pixels).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Extracts a color palette from a slice of RGB color bytes represented with `u8`, returning an error instead of panicking.
+///
+/// Uses the same default options as ['get_palette_rgb'](get_palette_rgb).
+///
+/// # Arguments
+/// - `pixels` - `u8` slice of pixels to extract the palette from.
+///
+/// # Examples
+///
+/// ```
+/// use palette_extract::{try_get_palette_rgb, PaletteError};
+///
+/// assert_eq!(try_get_palette_rgb(&[]), Err(PaletteError::EmptyInput));
+/// ```
+/// # Errors
+/// See ['try_get_palette_with_options'](try_get_palette_with_options).
+///
+pub fn try_get_palette_rgb(pixels: &[u8]) -> Result<Vec<Color>, PaletteError> {
+    try_get_palette_with_options(
         pixels,
         PixelEncoding::Rgb,
         Quality::default(),
@@ -169,3 +474,46 @@ pub fn get_palette_rgb(pixels: &[u8]) -> Vec<Color> {
         PixelFilter::None,
     )
 }
+
+/// Finds the most common color in a slice of RGB color bytes represented with `u8`. Uses ['Quality'](Quality) of 5 and ['PixelFilter::None'](PixelFilter::None).
+///
+/// This is the average color of the most populous cluster of similar colors, rather than of the first box of the palette.
+///
+/// See ['PaletteExtractor::dominant_color'](PaletteExtractor::dominant_color) to find the dominant color with other options.
+///
+/// # Arguments
+/// - `pixels` - `u8` slice of pixels to find the dominant color of.
+///
+/// # Examples
+///
+/// ```
+/// use palette_extract::{get_dominant_color, Color};
+/// let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 255, 0, 0, 255, 0, 0];
+///
+/// assert_eq!(get_dominant_color(&pixels), Color::new(252, 4, 4));
+/// ```
+/// # Panics
+/// Panics if the dominant color can't be found. See ['try_get_dominant_color'](try_get_dominant_color) for the conditions under which this happens.
+///
+pub fn get_dominant_color(pixels: &[u8]) -> Color {
+    try_get_dominant_color(pixels).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Finds the most common color in a slice of RGB color bytes represented with `u8`, returning an error instead of panicking.
+///
+/// Uses the same default options as ['get_dominant_color'](get_dominant_color).
+///
+/// # Arguments
+/// - `pixels` - `u8` slice of pixels to find the dominant color of.
+///
+/// # Errors
+/// See ['PaletteExtractor::dominant_color'](PaletteExtractor::dominant_color).
+///
+pub fn try_get_dominant_color(pixels: &[u8]) -> Result<Color, PaletteError> {
+    PaletteExtractor::builder()
+        .encoding(PixelEncoding::Rgb)
+        .quality(Quality::default())
+        .pixel_filter(PixelFilter::None)
+        .build()?
+        .dominant_color(pixels)
+}
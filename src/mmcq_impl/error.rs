@@ -0,0 +1,106 @@
+use std::fmt::{Display, Formatter};
+
+/// An error that can occur while extracting a color palette.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PaletteError {
+    /// The pixel buffer contains no pixels.
+    EmptyInput,
+
+    /// The length of the pixel buffer is not a multiple of the encoding's stride.
+    InvalidBufferLength {
+        /// The length of the pixel buffer, in elements (i.e. bytes for a `u8` buffer).
+        len: usize,
+
+        /// The number of elements that represent a single pixel in the requested encoding.
+        stride: u8,
+    },
+
+    /// The row stride of an image is too short to hold a row of pixels, or doesn't line up with the size of a sample.
+    InvalidRowStride {
+        /// The requested number of bytes per row.
+        bytes_per_row: usize,
+    },
+
+    /// The pixel buffer is too small for the dimensions of the image.
+    BufferTooSmall {
+        /// The length of the pixel buffer, in elements.
+        len: usize,
+
+        /// The number of elements needed to hold the image.
+        required: usize,
+    },
+
+    /// A mask or weight map doesn't have one entry for every pixel of the image.
+    InvalidWeightsLength {
+        /// The length of the weights.
+        len: usize,
+
+        /// The number of pixels in the image.
+        required: usize,
+    },
+
+    /// A region of an image extends past the edges of the image.
+    RegionOutOfBounds,
+
+    /// A quality of zero was requested. Quality is a sampling step and must be at least 1.
+    ZeroQuality,
+
+    /// A max color count of zero was requested.
+    ZeroMaxColors,
+
+    /// A number of histogram bits outside of 4 to 8 was requested.
+    InvalidHistogramBits {
+        /// The requested number of bits per channel.
+        bits: u8,
+    },
+
+    /// An alpha threshold of 255 was requested, which no pixel can exceed, so every pixel would be discarded.
+    InvalidAlphaThreshold,
+
+    /// Every sampled pixel was discarded, either for being transparent or by the pixel filter.
+    NoPixels,
+
+    /// A box of colors could not be split by the median cut.
+    UncuttableBox,
+}
+
+impl Display for PaletteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaletteError::EmptyInput => write!(f, "pixel buffer is empty"),
+            PaletteError::InvalidBufferLength { len, stride } => write!(
+                f,
+                "pixel buffer length {} is not a multiple of the pixel stride {}",
+                len, stride
+            ),
+            PaletteError::InvalidRowStride { bytes_per_row } => write!(
+                f,
+                "{} bytes per row is too short or not a multiple of the sample size",
+                bytes_per_row
+            ),
+            PaletteError::BufferTooSmall { len, required } => write!(
+                f,
+                "pixel buffer length {} is smaller than the {} needed for the image",
+                len, required
+            ),
+            PaletteError::InvalidWeightsLength { len, required } => write!(
+                f,
+                "weights have {} entries, but the image has {} pixels",
+                len, required
+            ),
+            PaletteError::RegionOutOfBounds => write!(f, "region is outside of the image"),
+            PaletteError::ZeroQuality => write!(f, "quality must be at least 1"),
+            PaletteError::ZeroMaxColors => write!(f, "max colors must be at least 1"),
+            PaletteError::InvalidHistogramBits { bits } => {
+                write!(f, "histogram bits must be from 4 to 8, got {}", bits)
+            }
+            PaletteError::InvalidAlphaThreshold => {
+                write!(f, "alpha threshold must be below 255")
+            }
+            PaletteError::NoPixels => write!(f, "no pixels left after filtering"),
+            PaletteError::UncuttableBox => write!(f, "vbox can't be cut"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+use super::histogram::{for_each_sample, sample_unit};
+use super::image_view::ImageView;
+use super::types::{Color, ColorBox, Swatch};
+use crate::{ExactColors, PaletteExtractor};
+
+/// Counts the exact colors of the pixels sampled from `view`, and returns the most populous ones as swatches, most populous first.
+///
+/// Returns `None` when the median cut should run instead: when the mode is ['ExactColors::Never'](ExactColors::Never), when ['ExactColors::Auto'](ExactColors::Auto) finds more distinct colors than the max number of colors, or when no pixels are sampled.
+pub fn exact_swatches(view: &ImageView, extractor: &PaletteExtractor) -> Option<Vec<Swatch>> {
+    let limit = match extractor.exact_colors {
+        ExactColors::Never => return None,
+        ExactColors::Auto => Some(extractor.max_colors as usize),
+        ExactColors::Always => None,
+    };
+
+    let mut counts: HashMap<(u8, u8, u8), u64> = HashMap::new();
+    let sampled = for_each_sample(view, extractor, |p, count| {
+        *counts.entry((p.r, p.g, p.b)).or_insert(0) += count;
+
+        if limit.is_some_and(|limit| counts.len() > limit) {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    if sampled.is_break() || counts.is_empty() {
+        return None;
+    }
+
+    let total: u64 = counts.values().sum();
+    let unit = sample_unit(view, extractor);
+
+    // ties are broken by color, so the order doesn't depend on the hash map
+    let mut counts: Vec<((u8, u8, u8), u64)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts.truncate(extractor.max_colors.into());
+
+    let swatches = counts
+        .into_iter()
+        .map(|((r, g, b), count)| {
+            let color = Color::new(r, g, b);
+
+            Swatch {
+                color,
+                population: (count + unit / 2) / unit,
+                proportion: (count as f64 / total as f64) as f32,
+                bounds: ColorBox {
+                    min: color,
+                    max: color,
+                },
+            }
+        })
+        .collect();
+
+    Some(swatches)
+}
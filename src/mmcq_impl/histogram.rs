@@ -1,59 +1,389 @@
-use std::{cmp, rc::Rc};
+use std::{
+    cmp,
+    ops::{ControlFlow, Range},
+    rc::Rc,
+};
 
 use super::{
-    config::{HISTOGRAM_SIZE, RIGHT_SHIFT},
-    util::color_index_from,
+    error::PaletteError,
+    image_view::ImageView,
+    moments::{self, Moments, SummedVolume},
     vbox::VBox,
-    PixelEncoding,
 };
+use super::types::Pixel;
+use crate::{ColorAverage, PaletteExtractor};
+
+/// The number of pixels in each cell of the color space, in multiples of `unit`.
+///
+/// Each channel is quantized to `bits` bits, so there are `2^bits` cells along each axis. With ['ColorAverage::PixelMean'](ColorAverage::PixelMean), the weighted sums of the channels of the pixels in each cell are kept too, which takes 3 times the memory of the counts.
+///
+/// Once every pixel has been added, ['build_moments'](Histogram::build_moments) turns the counts and sums into summed-volume tables in place, so the moments of any box take a handful of lookups.
+pub struct Histogram {
+    counts: SummedVolume<1>,
+    sums: Option<SummedVolume<3>>,
+    /// Whether any pixel has been added to each slice of cells across each axis, so the bounds of the pixels don't have to be tracked pixel by pixel. Sized for 8 bits, so indexing it takes no bounds check.
+    occupied: [[bool; 256]; 3],
+    summed: bool,
+    unit: u64,
+    bits: u8,
+}
+
+impl Histogram {
+    /// Creates an empty histogram with `bits` bits per channel.
+    pub fn new(bits: u8, unit: u64, color_average: ColorAverage) -> Histogram {
+        let side = 1 << bits;
+
+        Histogram {
+            counts: SummedVolume::new(side),
+            sums: match color_average {
+                ColorAverage::BinCenter => None,
+                ColorAverage::PixelMean => Some(SummedVolume::new(side)),
+            },
+            occupied: [[false; 256]; 3],
+            summed: false,
+            unit,
+            bits,
+        }
+    }
+
+    /// The count of a single, whole pixel. Larger than 1 when pixels are weighted.
+    pub fn unit(&self) -> u64 {
+        self.unit
+    }
+
+    /// The number of bits dropped from each 8-bit channel.
+    pub fn shift(&self) -> u8 {
+        8 - self.bits
+    }
+
+    /// The width of a cell, in 8-bit channel values.
+    pub fn multiplier(&self) -> u16 {
+        1 << self.shift()
+    }
+
+    /// The number of cells along each axis.
+    pub fn side(&self) -> usize {
+        1 << self.bits
+    }
+
+    /// Whether the moments hold the sums of the actual pixels, rather than the centers of the cells.
+    pub fn averages_pixels(&self) -> bool {
+        self.sums.is_some()
+    }
+
+    /// Adds `count` to the cell of `pixel`.
+    // too large to be inlined into the sampling loops otherwise
+    #[inline(always)]
+    pub fn add(&mut self, pixel: Pixel, count: u64) {
+        let shift = self.shift();
+        let (r, g, b) = (
+            u16::from(pixel.r >> shift),
+            u16::from(pixel.g >> shift),
+            u16::from(pixel.b >> shift),
+        );
+
+        self.counts.add(r, g, b, [count]);
+        for (occupied, c) in self.occupied.iter_mut().zip([r, g, b]) {
+            occupied[usize::from(c)] = true;
+        }
+        if let Some(sums) = &mut self.sums {
+            sums.add(r, g, b, [pixel.r, pixel.g, pixel.b].map(|c| count * c as u64));
+        }
+    }
+
+    /// The first and last cells along each axis any pixel has been added to, or `None` if no pixel has been added.
+    pub fn bounds(&self) -> Option<([u16; 3], [u16; 3])> {
+        let mut min = [0; 3];
+        let mut max = [0; 3];
+
+        for (c, occupied) in self.occupied.iter().enumerate() {
+            min[c] = occupied.iter().position(|&o| o)? as u16;
+            max[c] = occupied.iter().rposition(|&o| o)? as u16;
+        }
+
+        Some((min, max))
+    }
+
+    /// Turns the counts and sums into summed-volume tables, used by ['count'](Histogram::count) and ['moments'](Histogram::moments), covering the cells from `min` to `max`. Must be called once every pixel has been added, and every cell outside of the bounds must be empty.
+    ///
+    /// Until then, boxes are summed cell by cell.
+    pub fn build_moments(&mut self, min: [u16; 3], max: [u16; 3]) {
+        self.counts.accumulate(min, max);
+        if let Some(sums) = &mut self.sums {
+            sums.accumulate(min, max);
+        }
+
+        self.summed = true;
+    }
+
+    /// The sums of `table` over the box covering the cells in `r`, `g` and `b`.
+    fn sum<const N: usize>(
+        &self,
+        table: &SummedVolume<N>,
+        r: Range<u16>,
+        g: Range<u16>,
+        b: Range<u16>,
+    ) -> [u64; N] {
+        if self.summed {
+            return table.of(r, g, b);
+        }
+
+        let mut total = [0; N];
+        for r in r {
+            for g in g.clone() {
+                for b in b.clone() {
+                    total = moments::add(total, table.cell(r, g, b));
+                }
+            }
+        }
+
+        total
+    }
+
+    /// The number of pixels in the box covering the cells in `r`, `g` and `b`, in multiples of ['unit'](Histogram::unit).
+    pub fn count(&self, r: Range<u16>, g: Range<u16>, b: Range<u16>) -> u64 {
+        let [count] = self.sum(&self.counts, r, g, b);
+
+        count
+    }
+
+    /// The moments of the box covering the cells in `r`, `g` and `b`.
+    ///
+    /// The channels are twice the sum of the colors of the box, so the centers of cells a single value wide are whole numbers. The average color of a box is its channels divided by twice its count.
+    ///
+    /// With ['ColorAverage::PixelMean'](ColorAverage::PixelMean) this takes a constant number of lookups. With ['ColorAverage::BinCenter'](ColorAverage::BinCenter) the channels are derived from the counts of the slices of the box, one lookup per slice, so it takes time proportional to the size of the box. Keeping a table of the weighted counts along each axis instead would make it constant too, at 4 times the memory of the counts alone.
+    pub fn moments(&self, r: Range<u16>, g: Range<u16>, b: Range<u16>) -> Moments {
+        let count = self.count(r.clone(), g.clone(), b.clone());
+
+        if let Some(sums) = &self.sums {
+            let [r, g, b] = self.sum(sums, r, g, b);
+            return Moments {
+                count,
+                r: 2 * r,
+                g: 2 * g,
+                b: 2 * b,
+            };
+        }
+
+        // twice the center of a cell is (2 * channel + 1) * multiplier, so the sum of the centers
+        // along an axis follows from the counts of the slices of the box across it
+        let multiplier = self.multiplier() as u64;
+        let centers = |weighted: u64| (2 * weighted + count) * multiplier;
+
+        let r_weighted = r.clone().map(|c| c as u64 * self.count(c..c + 1, g.clone(), b.clone()));
+        let g_weighted = g.clone().map(|c| c as u64 * self.count(r.clone(), c..c + 1, b.clone()));
+        let b_weighted = b.clone().map(|c| c as u64 * self.count(r.clone(), g.clone(), c..c + 1));
+
+        Moments {
+            count,
+            r: centers(r_weighted.sum()),
+            g: centers(g_weighted.sum()),
+            b: centers(b_weighted.sum()),
+        }
+    }
+}
+
+/// An upper bound on the channels of the moments of a cell, in multiples of its count. See ['cell_moments'](Histogram::cell_moments).
+const MAX_MOMENT_PER_COUNT: u128 = 2 * 256;
+
+/// The number of bits the weights of `samples` sampled pixels, each worth up to `unit`, are shifted right by, so the moments of the whole histogram fit in a `u64`.
+///
+/// Only images sampling billions of pixels with fractional weights or an alpha policy that weighs pixels need it. A whole pixel is never scaled below 1.
+fn weight_shift(samples: usize, unit: u64) -> u32 {
+    let max_moment = samples as u128 * unit as u128 * MAX_MOMENT_PER_COUNT;
+    let excess_bits = (u128::BITS - max_moment.leading_zeros()).saturating_sub(u64::BITS);
+
+    excess_bits.min(unit.ilog2())
+}
+
+/// The number of bits the weights of the pixels sampled from `view` are shifted right by. See ['weight_shift'](weight_shift).
+fn sample_shift(view: &ImageView, extractor: &PaletteExtractor) -> u32 {
+    let samples = view.pixel_count().div_ceil(cmp::max(extractor.quality, 1).into());
+
+    weight_shift(samples, view.weight_unit() * extractor.alpha_policy.unit())
+}
+
+/// Calls `f` with each pixel sampled from `view`, and its weight in multiples of ['sample_unit'](sample_unit), after the alpha policy and pixel filter have been applied, until `f` breaks.
+pub fn for_each_sample(
+    view: &ImageView,
+    extractor: &PaletteExtractor,
+    mut f: impl FnMut(Pixel, u64) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    let shift = sample_shift(view, extractor);
+    // rounded to the nearest multiple, without discarding any sampled pixel
+    let scale = move |weight: u64| match shift {
+        0 => weight,
+        shift => ((weight + (1 << (shift - 1))) >> shift).max(1),
+    };
+
+    let step = cmp::max(extractor.quality, 1).into();
+    let policy = extractor.alpha_policy;
+    let threshold = extractor.alpha_threshold;
+    // the built-in filters come down to a range, so they take no match for every pixel
+    let discarded = extractor.pixel_filter.discarded_channels();
+    let predicate = extractor.pixel_filter.predicate();
+    let keeps = move |p: Pixel| {
+        let discards = discarded
+            .as_ref()
+            .is_some_and(|range| [p.r, p.g, p.b].iter().all(|c| range.contains(c)));
+        !discards && predicate.map_or(true, |predicate| predicate.keep(p))
+    };
+
+    view.for_each_pixel(step, extractor, #[inline(always)] move |p, weight| {
+        match policy.apply(p, threshold) {
+            Some((p, alpha_weight)) if keeps(p) => f(p, scale(weight * alpha_weight)),
+            _ => ControlFlow::Continue(()),
+        }
+    })
+}
+
+/// The weight of a single, whole pixel sampled from `view`.
+pub fn sample_unit(view: &ImageView, extractor: &PaletteExtractor) -> u64 {
+    (view.weight_unit() * extractor.alpha_policy.unit()) >> sample_shift(view, extractor)
+}
 
 pub fn create_histogram_and_vbox(
-    pixels: &[u8],
-    encoding: PixelEncoding,
-    quality: u8,
-    ignore_white: bool,
-) -> VBox {
-    let mut histogram: Vec<u32> = vec![0; HISTOGRAM_SIZE.into()];
+    view: &ImageView,
+    extractor: &PaletteExtractor,
+) -> Result<VBox, PaletteError> {
+    histogram_and_vbox(view, extractor, true)
+}
 
-    let mut r_min = u8::MAX;
-    let mut r_max = u8::MIN;
-    let mut g_min = u8::MAX;
-    let mut g_max = u8::MIN;
-    let mut b_min = u8::MAX;
-    let mut b_max = u8::MIN;
+/// Like ['create_histogram_and_vbox'](create_histogram_and_vbox), but without the summed-volume tables, so every box is summed cell by cell. The reference for the tables.
+#[cfg(test)]
+pub fn create_naive_histogram_and_vbox(
+    view: &ImageView,
+    extractor: &PaletteExtractor,
+) -> Result<VBox, PaletteError> {
+    histogram_and_vbox(view, extractor, false)
+}
+
+fn histogram_and_vbox(
+    view: &ImageView,
+    extractor: &PaletteExtractor,
+    summed: bool,
+) -> Result<VBox, PaletteError> {
+    let unit = sample_unit(view, extractor);
+    let mut histogram = Histogram::new(extractor.histogram_bits, unit, extractor.color_average);
+
+    // never breaks, since every sampled pixel is added
+    let _ = for_each_sample(view, extractor, #[inline(always)] |p, count| {
+        histogram.add(p, count);
+        ControlFlow::Continue(())
+    });
 
-    let pixel_count = pixels.len() / encoding.stride() as usize;
+    let ([r_min, g_min, b_min], [r_max, g_max, b_max]) =
+        histogram.bounds().ok_or(PaletteError::NoPixels)?;
 
-    let mut idx = 0;
+    if summed {
+        histogram.build_moments([r_min, g_min, b_min], [r_max, g_max, b_max]);
+    }
 
-    let quality_stride = quality as usize;
+    Ok(VBox::new(r_min, r_max, g_min, g_max, b_min, b_max, Rc::new(histogram)))
+}
 
-    while idx < pixel_count {
-        let p = encoding.extract_pixel(pixels, idx);
+#[cfg(test)]
+mod test {
+    use super::{weight_shift, Histogram, MAX_MOMENT_PER_COUNT};
+    use crate::mmcq_impl::types::Pixel;
+    use crate::ColorAverage;
 
-        if !p.is_opaque() || (ignore_white && p.is_white()) {
-            idx = idx.checked_add(quality_stride).unwrap(); // TODO: fix upwrap
-            continue;
+    /// A xorshift generator, so the histograms are reproducible.
+    fn random(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    /// A histogram of random pixels, with or without its summed-volume tables.
+    fn random_histogram(
+        bits: u8,
+        color_average: ColorAverage,
+        seed: u64,
+        summed: bool,
+    ) -> Histogram {
+        let mut seed = seed;
+        let mut histogram = Histogram::new(bits, 1, color_average);
+        for _ in 0..2000 {
+            // clustered away from the edges, so the tables don't cover the whole histogram
+            let channel = |seed: &mut u64| 40 + (random(seed) % 150) as u8;
+            let pixel = Pixel::new_rgb(channel(&mut seed), channel(&mut seed), channel(&mut seed));
+            histogram.add(pixel, 1 + random(&mut seed) % 1000);
+        }
+        if summed {
+            let (min, max) = histogram.bounds().unwrap();
+            histogram.build_moments(min, max);
         }
 
-        let shifted_r = p.r >> RIGHT_SHIFT;
-        let shifted_g = p.g >> RIGHT_SHIFT;
-        let shifted_b = p.b >> RIGHT_SHIFT;
+        histogram
+    }
+
+    #[test]
+    fn moments_match_naive_sums() {
+        let mut seed = 0x2545_f491_4f6c_dd1d;
+
+        for bits in 4..=8 {
+            for color_average in [ColorAverage::BinCenter, ColorAverage::PixelMean] {
+                let histogram = random_histogram(bits, color_average, seed, true);
+                let naive = random_histogram(bits, color_average, seed, false);
+                random(&mut seed);
 
-        r_min = cmp::min(r_min, shifted_r);
-        r_max = cmp::max(r_max, shifted_r);
-        g_min = cmp::min(g_min, shifted_g);
-        g_max = cmp::max(g_max, shifted_g);
-        b_min = cmp::min(b_min, shifted_b);
-        b_max = cmp::max(b_max, shifted_b);
+                let side = histogram.side() as u64;
 
-        // increment histgram
-        let index = color_index_from(shifted_r, shifted_g, shifted_b);
-        let x = histogram[index as usize];
-        histogram[index as usize] = x + 1;
+                // fewer boxes at higher precisions, where summing them cell by cell is slow
+                for _ in 0..(6400 >> bits) {
+                    // ranges can be empty, or extend past the tables on either side
+                    let range = |seed: &mut u64| {
+                        let start = (random(seed) % side) as u16;
+                        let end = (random(seed) % (side + 1)) as u16;
+                        start..end.max(start)
+                    };
+                    let (r, g, b) = (range(&mut seed), range(&mut seed), range(&mut seed));
 
-        idx = idx.checked_add(quality_stride).unwrap(); // TODO: fix upwrap
+                    assert_eq!(
+                        histogram.moments(r.clone(), g.clone(), b.clone()),
+                        naive.moments(r, g, b)
+                    );
+                }
+
+                // the whole histogram, except at 8 bits where summing it cell by cell is slow
+                if bits == 8 {
+                    continue;
+                }
+                let whole = 0..histogram.side() as u16;
+                assert_eq!(
+                    histogram.moments(whole.clone(), whole.clone(), whole.clone()),
+                    naive.moments(whole.clone(), whole.clone(), whole)
+                );
+            }
+        }
     }
 
-    VBox::new(r_min, r_max, g_min, g_max, b_min, b_max, Rc::new(histogram))
+    #[test]
+    fn weight_shift_keeps_moments_within_u64() {
+        // alpha weighted pixels with fractional weights
+        let unit = 255 << 16;
+
+        let max_moment = |samples: usize, shift: u32| {
+            samples as u128 * (unit >> shift) as u128 * MAX_MOMENT_PER_COUNT
+        };
+
+        assert_eq!(weight_shift(2_000_000_000, unit), 0);
+        assert_eq!(weight_shift(1 << 32, unit), 1);
+
+        for samples in [1 << 31, 3_000_000_000, 1 << 32, 1 << 40, 1 << 50] {
+            let shift = weight_shift(samples, unit);
+
+            assert!(max_moment(samples, shift) <= u64::MAX as u128, "{} samples", samples);
+            if shift > 0 {
+                assert!(max_moment(samples, shift - 1) > u64::MAX as u128, "{} samples", samples);
+            }
+        }
+
+        // a whole pixel is never scaled away
+        assert_eq!(weight_shift(usize::MAX, unit), unit.ilog2());
+        assert_eq!(weight_shift(usize::MAX, 1), 0);
+    }
 }
+
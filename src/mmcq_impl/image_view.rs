@@ -0,0 +1,493 @@
+use std::ops::ControlFlow;
+
+use super::{
+    error::PaletteError,
+    pixel_data::{f32_pixel, scale_u16},
+    pixel_encoding::unpremultiply,
+    types::Pixel,
+    ByteOrder, PixelData, PixelEncoding, PixelWeights, TransferFunction,
+};
+use crate::PaletteExtractor;
+
+/// A rectangle of pixels within an image, e.g. a region of interest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct Rect {
+    /// The column of the left edge of the rectangle.
+    pub x: usize,
+
+    /// The row of the top edge of the rectangle.
+    pub y: usize,
+
+    /// The width of the rectangle, in pixels.
+    pub width: usize,
+
+    /// The height of the rectangle, in pixels.
+    pub height: usize,
+}
+
+impl Rect {
+    /// Creates a new Rect struct.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use palette_extract::Rect;
+    ///
+    /// Rect::new(10, 20, 64, 48);
+    /// ```
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// A view of an image in a pixel buffer, whose rows may be padded.
+///
+/// GPU readbacks, video frames and sub-images of larger buffers often have padding at the end of each row. An `ImageView` describes where each row starts, so these buffers can be passed to a ['PaletteExtractor'](PaletteExtractor) without repacking them. Padding is never read.
+///
+/// A view carries its own ['PixelEncoding'](PixelEncoding), which takes precedence over the extractor's. Views of a region of the image can be created with ['region'](ImageView::region), and pixels can be masked out or weighted with ['with_weights'](ImageView::with_weights).
+///
+/// # Examples
+/// ```
+/// use palette_extract::{Color, ImageView, PaletteExtractor, PixelEncoding};
+///
+/// // a 2x2 RGB image, with each row padded to 8 bytes
+/// let pixels: [u8; 16] = [
+///     255, 0, 0, 255, 0, 0, 9, 9,
+///     255, 0, 0, 255, 0, 0, 9, 9,
+/// ];
+///
+/// let view = ImageView::with_bytes_per_row(&pixels, PixelEncoding::Rgb, 2, 2, 8).unwrap();
+///
+/// let palette = PaletteExtractor::default().extract(view).unwrap();
+///
+/// assert_eq!(palette, vec![Color::new(252, 4, 4)]);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct ImageView<'a> {
+    pixels: PixelData<'a>,
+    encoding: PixelEncoding,
+    width: usize,
+    height: usize,
+    /// The distance between the start of two rows, in samples.
+    row_stride: usize,
+    /// The sample the first pixel of the view starts at.
+    origin: usize,
+    weights: Option<PixelWeights<'a>>,
+    /// The entry of `weights` for the first pixel of the view.
+    weights_origin: usize,
+    /// The distance between the start of two rows of `weights`, in entries.
+    weights_stride: usize,
+    /// Overrides the extractor's transfer function, if set.
+    transfer_function: Option<TransferFunction>,
+}
+
+impl<'a> ImageView<'a> {
+    /// Creates a view of a tightly packed image, i.e. one without any padding between rows.
+    ///
+    /// # Errors
+    /// - [`PaletteError::BufferTooSmall`] if `pixels` can't hold `width * height` pixels. If the size of the image overflows a `usize`, `required` is `usize::MAX`.
+    pub fn new(
+        pixels: impl Into<PixelData<'a>>,
+        encoding: PixelEncoding,
+        width: usize,
+        height: usize,
+    ) -> Result<ImageView<'a>, PaletteError> {
+        let pixels = pixels.into();
+        let bytes_per_row = width
+            .checked_mul(encoding.stride() as usize * pixels.bytes_per_sample())
+            .ok_or(PaletteError::BufferTooSmall {
+                len: pixels.len(),
+                required: usize::MAX,
+            })?;
+
+        ImageView::with_bytes_per_row(pixels, encoding, width, height, bytes_per_row)
+    }
+
+    /// Creates a view of an image whose rows start every `bytes_per_row` bytes.
+    ///
+    /// The last row doesn't need to be padded.
+    ///
+    /// # Errors
+    /// - [`PaletteError::InvalidRowStride`] if `bytes_per_row` is shorter than a row of pixels, or not a multiple of the size of a sample (e.g. odd for 16-bit samples).
+    /// - [`PaletteError::BufferTooSmall`] if `pixels` can't hold `height` rows. If the size of the image overflows a `usize`, `required` is `usize::MAX`.
+    pub fn with_bytes_per_row(
+        pixels: impl Into<PixelData<'a>>,
+        encoding: PixelEncoding,
+        width: usize,
+        height: usize,
+        bytes_per_row: usize,
+    ) -> Result<ImageView<'a>, PaletteError> {
+        let pixels = pixels.into();
+        let bytes_per_sample = pixels.bytes_per_sample();
+        let row_len = width.checked_mul(encoding.stride() as usize);
+
+        if bytes_per_row % bytes_per_sample != 0
+            || row_len.map_or(true, |row_len| bytes_per_row / bytes_per_sample < row_len)
+        {
+            return Err(PaletteError::InvalidRowStride { bytes_per_row });
+        }
+
+        let row_len = row_len.unwrap_or_default();
+        let row_stride = bytes_per_row / bytes_per_sample;
+
+        let required = match height {
+            0 => 0,
+            h => (h - 1)
+                .checked_mul(row_stride)
+                .and_then(|n| n.checked_add(row_len))
+                .and_then(|n| n.checked_mul(pixels.elements_per_sample()))
+                .unwrap_or(usize::MAX),
+        };
+        if pixels.len() < required {
+            return Err(PaletteError::BufferTooSmall {
+                len: pixels.len(),
+                required,
+            });
+        }
+
+        Ok(ImageView {
+            pixels,
+            encoding,
+            width,
+            height,
+            row_stride,
+            origin: 0,
+            weights: None,
+            weights_origin: 0,
+            weights_stride: 0,
+            transfer_function: None,
+        })
+    }
+
+    /// Creates a single row view of a tightly packed buffer of pixels.
+    ///
+    /// # Errors
+    /// - [`PaletteError::InvalidBufferLength`] if the length of `pixels` is not a multiple of the size of a pixel.
+    fn packed(
+        pixels: PixelData<'a>,
+        encoding: PixelEncoding,
+    ) -> Result<ImageView<'a>, PaletteError> {
+        let stride = pixels.stride(encoding);
+        if pixels.len() % stride as usize != 0 {
+            return Err(PaletteError::InvalidBufferLength {
+                len: pixels.len(),
+                stride,
+            });
+        }
+
+        let width = pixels.len() / stride as usize;
+
+        ImageView::new(pixels, encoding, width, 1)
+    }
+
+    /// Creates a view of a rectangular region of this image, without copying any pixels.
+    ///
+    /// Useful for extracting the palette of a crop of a larger frame, like a bounding box or a banner.
+    ///
+    /// # Examples
+    /// ```
+    /// use palette_extract::{Color, ImageView, PaletteExtractor, PixelEncoding, Rect};
+    ///
+    /// // a 2x2 RGB image, with a blue pixel in the bottom right corner
+    /// let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 0, 255];
+    ///
+    /// let view = ImageView::new(&pixels, PixelEncoding::Rgb, 2, 2).unwrap();
+    /// let corner = view.region(Rect::new(1, 1, 1, 1)).unwrap();
+    ///
+    /// let palette = PaletteExtractor::default().extract(corner).unwrap();
+    ///
+    /// assert_eq!(palette, vec![Color::new(4, 4, 252)]);
+    /// ```
+    ///
+    /// # Errors
+    /// - [`PaletteError::RegionOutOfBounds`] if `rect` doesn't fit within this view.
+    pub fn region(&self, rect: Rect) -> Result<ImageView<'a>, PaletteError> {
+        let fits = |start: usize, len: usize, max: usize| {
+            start.checked_add(len).is_some_and(|end| end <= max)
+        };
+        if !fits(rect.x, rect.width, self.width) || !fits(rect.y, rect.height, self.height) {
+            return Err(PaletteError::RegionOutOfBounds);
+        }
+
+        Ok(ImageView {
+            width: rect.width,
+            height: rect.height,
+            origin: self.offset_of(rect.x, rect.y),
+            weights_origin: self.weights_origin + rect.y * self.weights_stride + rect.x,
+            ..*self
+        })
+    }
+
+    /// Attaches a mask or weight map to this view, with one entry for each of its pixels, row by row.
+    ///
+    /// Pixels with a weight of zero are discarded, and the rest count towards the palette in proportion to their weight, e.g. to keep only the foreground of a segmentation mask, or to down-weight the background. Pixel filters still apply on top. If a region of the view is created afterwards, the weights are cropped along with the pixels.
+    ///
+    /// # Examples
+    /// ```
+    /// use palette_extract::{Color, ImageView, PaletteExtractor, PixelEncoding, Quality};
+    ///
+    /// // two red pixels and two blue pixels
+    /// let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 0, 0, 255, 0, 0, 255];
+    /// let mask = [false, false, true, true];
+    ///
+    /// let view = ImageView::new(&pixels, PixelEncoding::Rgb, 2, 2)
+    ///     .unwrap()
+    ///     .with_weights(&mask)
+    ///     .unwrap();
+    ///
+    /// let extractor = PaletteExtractor::builder().quality(Quality::new(1)).build().unwrap();
+    /// let swatches = extractor.extract_swatches(view).unwrap();
+    ///
+    /// assert_eq!(swatches[0].color, Color::new(4, 4, 252));
+    /// assert_eq!(swatches[0].population, 2);
+    /// ```
+    ///
+    /// # Errors
+    /// - [`PaletteError::InvalidWeightsLength`] if `weights` doesn't have exactly one entry per pixel.
+    pub fn with_weights(
+        self,
+        weights: impl Into<PixelWeights<'a>>,
+    ) -> Result<ImageView<'a>, PaletteError> {
+        let weights = weights.into();
+        let required = self.pixel_count();
+        if weights.len() != required {
+            return Err(PaletteError::InvalidWeightsLength {
+                len: weights.len(),
+                required,
+            });
+        }
+
+        Ok(ImageView {
+            weights: Some(weights),
+            weights_origin: 0,
+            weights_stride: self.width,
+            ..self
+        })
+    }
+
+    /// Sets how the color channels of floating point pixels are encoded, taking precedence over the extractor's ['TransferFunction'](TransferFunction). Ignored for integer pixels.
+    ///
+    /// Useful when the encoding is known from where the pixels came from, e.g. floating point images of the `image` crate, which are sRGB encoded.
+    pub fn with_transfer_function(self, transfer_function: TransferFunction) -> ImageView<'a> {
+        ImageView {
+            transfer_function: Some(transfer_function),
+            ..self
+        }
+    }
+
+    /// The width of the image, in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the image, in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// How pixels are represented in the image.
+    pub fn encoding(&self) -> PixelEncoding {
+        self.encoding
+    }
+
+    /// The distance between the start of two rows, in bytes.
+    pub fn bytes_per_row(&self) -> usize {
+        self.row_stride * self.pixels.bytes_per_sample()
+    }
+
+    /// The number of pixels in the image.
+    ///
+    /// Can't overflow, since every pixel of a non-empty row takes up at least one element of the buffer.
+    pub(crate) fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// The weight of a whole pixel in the histogram.
+    pub(crate) fn weight_unit(&self) -> u64 {
+        self.weights.map_or(1, |w| w.unit())
+    }
+
+    /// Calls `f` with every `step`th pixel of the image, counting row by row, and its weight in multiples of ['weight_unit'](ImageView::weight_unit), until `f` breaks. Pixels with a weight of zero are skipped.
+    ///
+    /// The type of the samples, the layout of the channels and whether the image is weighted are matched once per image rather than once per pixel, and what runs for every pixel is inlined into each of the loops this specializes into.
+    pub(crate) fn for_each_pixel(
+        &self,
+        step: usize,
+        extractor: &PaletteExtractor,
+        f: impl FnMut(Pixel, u64) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let encoding = self.encoding;
+        let transfer_function = self.transfer_function.unwrap_or(extractor.transfer_function);
+        let tone_mapping = extractor.tone_mapping;
+
+        match self.pixels {
+            PixelData::U8(samples) => self.for_each_integer(
+                step,
+                f,
+                u8::MAX.into(),
+                move |i| samples[i].into(),
+                |v| v as u8,
+            ),
+            PixelData::U16(samples) => self.for_each_integer(
+                step,
+                f,
+                u16::MAX.into(),
+                move |i| samples[i].into(),
+                |v| scale_u16(v as u16),
+            ),
+            PixelData::U16Bytes(bytes, ByteOrder::LittleEndian) => self.for_each_integer(
+                step,
+                f,
+                u16::MAX.into(),
+                move |i| u16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]]).into(),
+                |v| scale_u16(v as u16),
+            ),
+            PixelData::U16Bytes(bytes, ByteOrder::BigEndian) => self.for_each_integer(
+                step,
+                f,
+                u16::MAX.into(),
+                move |i| u16::from_be_bytes([bytes[i * 2], bytes[i * 2 + 1]]).into(),
+                |v| scale_u16(v as u16),
+            ),
+            PixelData::F32(samples) => {
+                self.for_each_weighted(step, f, #[inline(always)] move |offset| {
+                    f32_pixel(samples, encoding, offset, transfer_function, tone_mapping)
+                })
+            }
+        }
+    }
+
+    /// Like ['for_each_pixel'](ImageView::for_each_pixel), for integer samples, where `sample(i)` returns the `i`th sample, `max` is the value of a fully opaque alpha, and `scale` scales a sample down to 8 bits.
+    ///
+    /// The positions of the channels are worked out once, so opaque and straight alpha pixels are read without un-premultiplying them.
+    fn for_each_integer(
+        &self,
+        step: usize,
+        f: impl FnMut(Pixel, u64) -> ControlFlow<()>,
+        max: u32,
+        sample: impl Fn(usize) -> u32,
+        scale: impl Fn(u32) -> u8,
+    ) -> ControlFlow<()> {
+        let [r, g, b] = self.encoding.color_channels();
+
+        match self.encoding.alpha_channel() {
+            None => self.for_each_weighted(step, f, #[inline(always)] move |offset| {
+                Pixel::new_rgb(
+                    scale(sample(offset + r)),
+                    scale(sample(offset + g)),
+                    scale(sample(offset + b)),
+                )
+            }),
+            Some(a) if !self.encoding.is_premultiplied() => {
+                self.for_each_weighted(step, f, #[inline(always)] move |offset| {
+                    Pixel::new_rgba(
+                        scale(sample(offset + r)),
+                        scale(sample(offset + g)),
+                        scale(sample(offset + b)),
+                        scale(sample(offset + a)),
+                    )
+                })
+            }
+            Some(a) => self.for_each_weighted(step, f, #[inline(always)] move |offset| {
+                let alpha = sample(offset + a);
+                let straight = |c: usize| scale(unpremultiply(sample(offset + c), alpha, max));
+
+                Pixel::new_rgba(straight(r), straight(g), straight(b), scale(alpha))
+            }),
+        }
+    }
+
+    /// Like ['for_each_pixel'](ImageView::for_each_pixel), where `pixel(offset)` extracts the pixel starting at the `offset`th sample.
+    fn for_each_weighted(
+        &self,
+        step: usize,
+        mut f: impl FnMut(Pixel, u64) -> ControlFlow<()>,
+        pixel: impl Fn(usize) -> Pixel,
+    ) -> ControlFlow<()> {
+        match self.weights {
+            None => {
+                self.for_each_offset(step, #[inline(always)] move |offset, _| f(pixel(offset), 1))
+            }
+            Some(weights) => {
+                self.for_each_offset(step, #[inline(always)] move |offset, index| {
+                    match weights.weight(index) {
+                        0 => ControlFlow::Continue(()),
+                        weight => f(pixel(offset), weight),
+                    }
+                })
+            }
+        }
+    }
+
+    /// Calls `f` with the sample every `step`th pixel of the image starts at, counting row by row, and the index of its weight, until `f` breaks.
+    fn for_each_offset(
+        &self,
+        step: usize,
+        mut f: impl FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let (width, height) = (self.width, self.height);
+        if width == 0 {
+            return ControlFlow::Continue(());
+        }
+
+        let offset_step = step * self.encoding.stride() as usize;
+        // the column of the next pixel, which runs past the end of the row when the step does
+        let mut x = 0;
+        let mut y = 0;
+
+        while y < height {
+            let mut offset = self.offset_of(x, y);
+            let weights_row = self.weights_origin + y * self.weights_stride;
+
+            while x < width {
+                f(offset, weights_row + x)?;
+                x += step;
+                offset += offset_step;
+            }
+
+            y += x / width;
+            x %= width;
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// The sample the pixel at column `x` and row `y` starts at.
+    fn offset_of(&self, x: usize, y: usize) -> usize {
+        self.origin + y * self.row_stride + x * self.encoding.stride() as usize
+    }
+}
+
+/// Conversion into an ['ImageView'](ImageView), for anything that can be passed to a ['PaletteExtractor'](PaletteExtractor).
+///
+/// Implemented for image views, and for tightly packed ['PixelData'](PixelData) (including slices of `u8`, `u16` and `f32`), which is read with the extractor's encoding.
+pub trait IntoImageView<'a> {
+    /// Creates the view, using `encoding` if the pixels don't specify their own.
+    ///
+    /// # Errors
+    /// - [`PaletteError::InvalidBufferLength`] if the length of a tightly packed buffer is not a multiple of the size of a pixel.
+    fn into_image_view(self, encoding: PixelEncoding) -> Result<ImageView<'a>, PaletteError>;
+}
+
+impl<'a> IntoImageView<'a> for ImageView<'a> {
+    fn into_image_view(self, _encoding: PixelEncoding) -> Result<ImageView<'a>, PaletteError> {
+        Ok(self)
+    }
+}
+
+impl<'a> IntoImageView<'a> for &ImageView<'a> {
+    fn into_image_view(self, _encoding: PixelEncoding) -> Result<ImageView<'a>, PaletteError> {
+        Ok(*self)
+    }
+}
+
+impl<'a, T: Into<PixelData<'a>>> IntoImageView<'a> for T {
+    fn into_image_view(self, encoding: PixelEncoding) -> Result<ImageView<'a>, PaletteError> {
+        ImageView::packed(self.into(), encoding)
+    }
+}
@@ -1,128 +1,166 @@
 mod config;
+mod error;
+mod exact_colors;
 mod histogram;
+mod image_view;
+mod moments;
+mod pixel_data;
 mod pixel_encoding;
+mod pixel_weights;
+mod queue;
 mod types;
-mod util;
 mod vbox;
 
-pub use types::Color;
+pub use error::PaletteError;
+pub use image_view::{ImageView, IntoImageView, Rect};
+pub use types::{Color, ColorBox, Pixel, Swatch};
+pub use pixel_data::{ByteOrder, PixelData, ToneMapping, TransferFunction};
 pub use pixel_encoding::PixelEncoding;
+pub use pixel_weights::PixelWeights;
 
-use std::cmp::{self, Ordering};
+use std::cmp;
 
+use crate::PaletteExtractor;
+
+use exact_colors::exact_swatches;
 use histogram::create_histogram_and_vbox;
-use config::{FRACTION_BY_POPULATION, MAX_ITERATIONS, VBOX_LENGTH};
-use util::color_index_from;
+use config::{FRACTION_BY_POPULATION, MAX_ITERATIONS};
+use queue::{BoxQueue, Priority};
 use vbox::VBox;
 use types::ColorChannel;
 
-pub fn extract_colors(
-    pixels: &[u8],
-    encoding: PixelEncoding,
-    quality: u8,
-    max_colors: u8,
-    ignore_white: bool,
-) -> Vec<Color> {
-    let vbox = create_histogram_and_vbox(
-        pixels,
-        encoding,
-        quality,
-        ignore_white,
-    );
-
-    // priority queue
-    let mut pq = vec![vbox];
+pub fn extract_swatches(
+    view: &ImageView,
+    extractor: &PaletteExtractor,
+) -> Result<Vec<Swatch>, PaletteError> {
+    if let Some(swatches) = exact_swatches(view, extractor) {
+        return Ok(swatches);
+    }
+
+    median_cut_swatches(histogram_for(view, extractor)?, extractor.max_colors)
+}
+
+/// Runs the median cut over the histogram in `vbox`, returning the swatches in palette order.
+fn median_cut_swatches(vbox: VBox, max_colors: u8) -> Result<Vec<Swatch>, PaletteError> {
+    let total = vbox.get_count();
+    let boxes = quantize(vbox, max_colors)?;
+
+    Ok(boxes.iter().map(|v| v.to_swatch(total)).collect())
+}
+
+/// Finds the most populous box of the median cut, or the most populous exact color.
+pub fn extract_dominant_swatch(
+    view: &ImageView,
+    extractor: &PaletteExtractor,
+) -> Result<Swatch, PaletteError> {
+    if let Some(swatches) = exact_swatches(view, extractor) {
+        return Ok(swatches[0]);
+    }
+
+    let vbox = histogram_for(view, extractor)?;
+    let total = vbox.get_count();
+
+    Ok(most_populous_box(vbox, extractor.max_colors)?.to_swatch(total))
+}
+
+fn histogram_for(view: &ImageView, extractor: &PaletteExtractor) -> Result<VBox, PaletteError> {
+    if view.pixel_count() == 0 {
+        return Err(PaletteError::EmptyInput);
+    }
+
+    create_histogram_and_vbox(view, extractor)
+}
 
+/// The number of boxes the first phase of the median cut, which cuts the most populous box, stops at.
+fn population_target(max_colors: u8) -> u32 {
     // Round up to have the same behaviour as in JavaScript
-    let target = (FRACTION_BY_POPULATION * max_colors as f32).ceil() as u32;
+    (FRACTION_BY_POPULATION * max_colors as f32).ceil() as u32
+}
+
+/// Runs only the first phase of the median cut over the histogram in `vbox`, returning the most populous box it leaves. The second phase only cuts boxes by volume, so it's skipped.
+fn most_populous_box(vbox: VBox, max_colors: u8) -> Result<VBox, PaletteError> {
+    let mut pq = BoxQueue::new(Priority::Count);
+    pq.push(vbox);
+
+    iterate(&mut pq, population_target(max_colors))?;
+
+    pq.pop().ok_or(PaletteError::NoPixels)
+}
+
+/// Runs the median cut over the histogram in `vbox`, returning the boxes in palette order.
+fn quantize(vbox: VBox, max_colors: u8) -> Result<Vec<VBox>, PaletteError> {
+    let mut pq = BoxQueue::new(Priority::Count);
+    pq.push(vbox);
 
-    iterate(&mut pq, sort_by_count, target);
+    iterate(&mut pq, population_target(max_colors))?;
 
-    pq.sort_by(sort_by_product);
+    let mut pq = pq.reprioritize(Priority::Product);
 
     let len_before = pq.len() as u32;
 
-    iterate(&mut pq, sort_by_product, max_colors as u32 - len_before);
+    // the first pass can overshoot the target, in which case there is nothing left to cut
+    if let Some(remaining) = (max_colors as u32).checked_sub(len_before) {
+        iterate(&mut pq, remaining)?;
+    }
 
-    pq.reverse();
+    let mut boxes = pq.into_sorted_vec();
+    boxes.reverse();
 
-    pq.iter().map(|v| v.get_average()).collect()
+    Ok(boxes)
 }
 
-fn apply_median_cut(vbox: VBox) -> Vec<VBox> {
+fn apply_median_cut(vbox: VBox) -> Result<Vec<VBox>, PaletteError> {
     if vbox.get_count() == 0 {
-        return vec![];
+        return Ok(vec![]);
     }
 
     // only one pixel, no split
-    if vbox.get_count() == 1 {
-        return vec![vbox];
+    if vbox.get_count() <= vbox.histogram.unit() {
+        return Ok(vec![vbox]);
     }
 
     let histogram = &vbox.histogram;
 
     // Find the partial sum arrays along the selected axis.
-    let mut total: u32 = 0;
-    let mut partial_sum: Vec<i32> = vec![-1; VBOX_LENGTH.into()]; // -1 = not set / 0 = 0
+    let mut total: u64 = 0;
+    let mut partial_sum: Vec<i64> = vec![-1; histogram.side()]; // -1 = not set / 0 = 0
     let axis = vbox.widest_color_channel();
 
-    match axis {
-        ColorChannel::R => {
-            for r in vbox.r_range() {
-                let mut sum: u32 = 0;
-                for g in vbox.g_range() {
-                    for b in vbox.b_range() {
-                        let index = color_index_from(r, g, b);
-                        sum += histogram[index as usize];
-                    }
-                }
-                total += sum;
-                partial_sum[r as usize] = total as i32;
-            }
-        }
-        ColorChannel::G => {
-            for g in vbox.g_range() {
-                let mut sum: u32 = 0;
-                for r in vbox.r_range() {
-                    for b in vbox.b_range() {
-                        let index = color_index_from(r, g, b);
-                        sum += histogram[index as usize];
-                    }
-                }
-                total += sum;
-                partial_sum[g as usize] = total as i32;
-            }
-        }
-        ColorChannel::B => {
-            for b in vbox.b_range() {
-                let mut sum: u32 = 0;
-                for r in vbox.r_range() {
-                    for g in vbox.g_range() {
-                        let index = color_index_from(r, g, b);
-                        sum += histogram[index as usize];
-                    }
-                }
-                total += sum;
-                partial_sum[b as usize] = total as i32;
-            }
+    // the count of each slice of the box along the axis
+    let slice_count = |i: u16| {
+        let slice = i..i + 1;
+        match axis {
+            ColorChannel::R => histogram.count(slice, vbox.g_range(), vbox.b_range()),
+            ColorChannel::G => histogram.count(vbox.r_range(), slice, vbox.b_range()),
+            ColorChannel::B => histogram.count(vbox.r_range(), vbox.g_range(), slice),
         }
+    };
+    let range = match axis {
+        ColorChannel::R => vbox.r_range(),
+        ColorChannel::G => vbox.g_range(),
+        ColorChannel::B => vbox.b_range(),
+    };
+
+    for i in range {
+        total += slice_count(i);
+        partial_sum[i as usize] = total as i64;
     }
 
-    let mut look_ahead_sum: Vec<i32> = vec![-1; VBOX_LENGTH.into()]; // -1 = not set / 0 = 0
+    let mut look_ahead_sum: Vec<i64> = vec![-1; histogram.side()]; // -1 = not set / 0 = 0
     for (i, sum) in partial_sum.iter().enumerate().filter(|(_, &sum)| sum != -1) {
-        look_ahead_sum[i] = total as i32 - sum
+        look_ahead_sum[i] = total as i64 - sum
     }
 
-    return cut(axis, &vbox, &partial_sum, &look_ahead_sum, total);
+    cut(axis, &vbox, &partial_sum, &look_ahead_sum, total)
 }
 
 fn cut(
     axis: ColorChannel,
     vbox: &VBox,
-    partial_sum: &[i32],
-    look_ahead_sum: &[i32],
-    total: u32,
-) -> Vec<VBox> {
+    partial_sum: &[i64],
+    look_ahead_sum: &[i64],
+    total: u64,
+) -> Result<Vec<VBox>, PaletteError> {
     let vbox_min: i32;
     let vbox_max: i32;
 
@@ -141,85 +179,156 @@ fn cut(
         }
     }
 
-    for l in (vbox_min..(vbox_max + 1)).filter(|&i| partial_sum[i as usize] > (total / 2) as i32) {
-        let mut vbox1 = VBox::new_from(&vbox);
-        let mut vbox2 = VBox::new_from(&vbox);
-
-        let left = l - vbox_min;
-        let right = vbox_max - l;
+    let l = (vbox_min..(vbox_max + 1))
+        .find(|&i| partial_sum[i as usize] > (total / 2) as i64)
+        .ok_or(PaletteError::UncuttableBox)?;
 
-        let mut d2 = if left <= right {
-            cmp::min(vbox_max - 1, l + right / 2)
-        } else {
-            // 2.0 and cast to int is necessary to have the same
-            // behaviour as in JavaScript
-            cmp::max(vbox_min, (((l - 1) as f32) - (left as f32 / 2.0)) as i32)
-        };
+    let mut vbox1 = VBox::new_from(vbox);
+    let mut vbox2 = VBox::new_from(vbox);
 
-        while d2 < 0 || partial_sum[d2 as usize] <= 0 {
-            d2 += 1;
-        }
+    let left = l - vbox_min;
+    let right = vbox_max - l;
 
-        let mut count2: i32 = look_ahead_sum[d2 as usize];
-        while count2 == 0 && d2 > 0 && partial_sum[(d2 - 1) as usize] > 0 {
-            d2 -= 1;
-            count2 = look_ahead_sum[d2 as usize];
+    let mut d2 = if left <= right {
+        cmp::min(vbox_max - 1, l + right / 2)
+    } else {
+        // 2.0 and cast to int is necessary to have the same
+        // behaviour as in JavaScript
+        cmp::max(vbox_min, (((l - 1) as f32) - (left as f32 / 2.0)) as i32)
+    };
+
+    while d2 < 0 || partial_sum[d2 as usize] <= 0 {
+        d2 += 1;
+        if d2 > vbox_max {
+            return Err(PaletteError::UncuttableBox);
         }
-
-        vbox1.set_max(d2 as u8, &axis);
-        vbox2.set_min(d2 as u8 + 1, &axis);
-
-        return vec![vbox1, vbox2];
     }
 
-    panic!("VBox can't be cut")
-}
-
-fn sort_by_count(l: &VBox, r: &VBox) -> Ordering {
-    l.get_count().cmp(&r.get_count())
-}
+    let mut count2: i64 = look_ahead_sum[d2 as usize];
+    while count2 == 0 && d2 > 0 && partial_sum[(d2 - 1) as usize] > 0 {
+        d2 -= 1;
+        count2 = look_ahead_sum[d2 as usize];
+    }
 
-fn sort_by_product(a: &VBox, b: &VBox) -> Ordering {
-    let a_count = a.get_count();
-    let b_count = b.get_count();
-    let a_volume = a.get_volume();
-    let b_volume = b.get_volume();
+    vbox1.set_max(d2 as u16, &axis);
+    vbox2.set_min(d2 as u16 + 1, &axis);
 
-    if a_count == b_count {
-        // If count is 0 for both (or the same), sort by volume
-        return a_volume.cmp(&b_volume);
-    } else {
-        // Otherwise sort by products
-        let a_product = a_count as u64 * a_volume as u64;
-        let b_product = b_count as u64 * b_volume as u64;
-        return a_product.cmp(&b_product);
-    }
+    Ok(vec![vbox1, vbox2])
 }
 
-fn iterate(queue: &mut Vec<VBox>, comp: fn(&VBox, &VBox) -> Ordering, target: u32) {
+fn iterate(queue: &mut BoxQueue, target: u32) -> Result<(), PaletteError> {
     let mut color = 1;
 
     for _ in 0..MAX_ITERATIONS {
-        let last_item = match queue.last() {
+        // the most important box is empty, so every box is and there's nothing left to cut
+        if queue.peek().map_or(true, |vbox| vbox.get_count() == 0) {
+            return Ok(());
+        }
+
+        let vbox = match queue.pop() {
             Some(v) => v,
-            None => return,
+            None => return Ok(()),
         };
-
-        if last_item.get_count() == 0 {
-            queue.sort_by(comp);
-            continue;
+        let mut new_boxes = apply_median_cut(vbox)?.into_iter();
+        if let Some(vbox1) = new_boxes.next() {
+            queue.push(vbox1);
         }
-        let vbox = queue.pop().unwrap();
-        let mut new_boxes = apply_median_cut(vbox);
-        queue.push(new_boxes.remove(0));
-        if new_boxes.len() == 1 {
-            queue.push(new_boxes.remove(0));
+        if let Some(vbox2) = new_boxes.next() {
+            queue.push(vbox2);
             color += 1
         }
-        queue.sort_by(comp);
 
         if color >= target {
-            return;
+            return Ok(());
         }
     }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::histogram::create_naive_histogram_and_vbox;
+    use super::types::Color;
+    use super::vbox::{test::synthetic_histogram, VBox};
+    use super::{extract_swatches, median_cut_swatches, quantize, ImageView, PixelEncoding};
+    use crate::{ColorAverage, MaxColors, PaletteExtractor, PixelFilter, Quality};
+
+    /// A 64x64 RGB image of noise around a few nearby colors, so the histogram covers few enough cells to be summed one by one.
+    fn clustered_image() -> Vec<u8> {
+        let mut seed: u32 = 0x9e37_79b9;
+        let mut noise = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            (seed % 16) as u8
+        };
+        let centers: [[u8; 3]; 4] =
+            [[110, 100, 96], [132, 120, 100], [100, 134, 120], [125, 105, 136]];
+
+        (0..64 * 64)
+            .flat_map(|i| centers[i % 7 % 4])
+            .map(|channel| channel - 8 + noise())
+            .collect()
+    }
+
+    #[test]
+    fn summed_volume_tables_match_naive_sums() {
+        let pixels = clustered_image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 64, 64).unwrap();
+
+        for bits in 4..=8 {
+            for color_average in [ColorAverage::BinCenter, ColorAverage::PixelMean] {
+                for max_colors in [2, 7, 24] {
+                    let extractor = PaletteExtractor::builder()
+                        .quality(Quality::new(1))
+                        .max_colors(MaxColors::new(max_colors))
+                        .pixel_filter(PixelFilter::None)
+                        .histogram_bits(bits)
+                        .color_average(color_average)
+                        .build()
+                        .unwrap();
+
+                    let naive = create_naive_histogram_and_vbox(&view, &extractor).unwrap();
+
+                    assert_eq!(
+                        extract_swatches(&view, &extractor).unwrap(),
+                        median_cut_swatches(naive, max_colors).unwrap(),
+                        "{} bits, {:?}, {} colors",
+                        bits,
+                        color_average,
+                        max_colors
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quantizes_multi_gigapixel_histogram() {
+        let histogram = synthetic_histogram(&[
+            ((31, 0, 0), 3_000_000_000),
+            ((0, 31, 0), 5_000_000_000),
+            ((0, 0, 31), 7_000_000_000),
+        ]);
+        let vbox = VBox::new(0, 31, 0, 31, 0, 31, histogram);
+
+        let boxes = quantize(vbox, 3).unwrap();
+
+        let mut counts: Vec<(Color, u64)> = boxes
+            .iter()
+            .filter(|v| v.get_count() > 0)
+            .map(|v| (v.get_average(), v.get_count()))
+            .collect();
+        counts.sort_by_key(|&(_, count)| count);
+
+        assert_eq!(
+            counts,
+            vec![
+                (Color::new(252, 4, 4), 3_000_000_000),
+                (Color::new(4, 252, 4), 5_000_000_000),
+                (Color::new(4, 4, 252), 7_000_000_000),
+            ]
+        );
+    }
 }
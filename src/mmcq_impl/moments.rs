@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+/// The count of a box of the histogram, and the numerators of its average color.
+///
+/// The moments of the whole histogram always fit in a `u64`, since sampling scales down the weights of images large enough to overflow them, so the moments of any box do too.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Moments {
+    pub count: u64,
+    pub r: u64,
+    pub g: u64,
+    pub b: u64,
+}
+
+/// `N` values for each cell of a histogram with `side` cells along each axis, which can be turned into a summed-volume table in place, so the sum of any box takes 8 lookups instead of a loop over its cells.
+///
+/// Cells are padded with a plane of zeros before the first cell along each axis. Once ['accumulate'](SummedVolume::accumulate) has run, the entry for `(r, g, b)` holds the sum of every cell up to, but not including, `(r, g, b)`. Only the entries within the bounds of the non-empty cells are accumulated, and boxes are clamped to them. Sums wrap around, but the sum of a box comes out exact as long as it fits in a `u64`.
+pub struct SummedVolume<const N: usize> {
+    side: usize,
+    entries: Vec<[u64; N]>,
+    /// The first and last accumulated entries along each axis, in padded coordinates.
+    bounds: [(usize, usize); 3],
+}
+
+impl<const N: usize> SummedVolume<N> {
+    /// Creates a table of `side` cells along each axis, all zero.
+    pub fn new(side: usize) -> SummedVolume<N> {
+        SummedVolume {
+            side,
+            entries: vec![[0; N]; (side + 1).pow(3)],
+            bounds: [(0, 0); 3],
+        }
+    }
+
+    /// The index of the entry at `(i, j, k)`, in padded coordinates.
+    fn index_of(&self, i: usize, j: usize, k: usize) -> usize {
+        (i * (self.side + 1) + j) * (self.side + 1) + k
+    }
+
+    /// Adds `values` to the cell at `(r, g, b)`. Only valid before ['accumulate'](SummedVolume::accumulate).
+    pub fn add(&mut self, r: u16, g: u16, b: u16, values: [u64; N]) {
+        let index = self.index_of(r as usize + 1, g as usize + 1, b as usize + 1);
+        self.entries[index] = add(self.entries[index], values);
+    }
+
+    /// The values of the cell at `(r, g, b)`. Only valid before ['accumulate'](SummedVolume::accumulate).
+    pub fn cell(&self, r: u16, g: u16, b: u16) -> [u64; N] {
+        self.entries[self.index_of(r as usize + 1, g as usize + 1, b as usize + 1)]
+    }
+
+    /// Turns the values of the cells into the summed-volume table, one axis at a time. Every cell outside of `min` to `max` inclusive must be zero.
+    pub fn accumulate(&mut self, min: [u16; 3], max: [u16; 3]) {
+        // the entry before the first cell is always zero, and the one after the last cell holds
+        // the sums of every cell along the axis
+        self.bounds = [0, 1, 2].map(|c| (min[c] as usize, max[c] as usize + 1));
+        let [(r0, r1), (g0, g1), (b0, b1)] = self.bounds;
+        let len = b1 - b0 + 1;
+
+        for i in r0..=r1 {
+            for j in g0..=g1 {
+                let start = self.index_of(i, j, b0);
+                for k in start + 1..start + len {
+                    self.entries[k] = add(self.entries[k], self.entries[k - 1]);
+                }
+            }
+        }
+        for i in r0..=r1 {
+            for j in g0 + 1..=g1 {
+                self.add_entries(self.index_of(i, j, b0), self.index_of(i, j - 1, b0), len);
+            }
+        }
+        for i in r0 + 1..=r1 {
+            for j in g0..=g1 {
+                self.add_entries(self.index_of(i, j, b0), self.index_of(i - 1, j, b0), len);
+            }
+        }
+    }
+
+    /// Adds the `len` entries starting at `from` to the ones starting at `to`, which come after them.
+    fn add_entries(&mut self, to: usize, from: usize, len: usize) {
+        let (before, after) = self.entries.split_at_mut(to);
+        for (entry, previous) in after[..len].iter_mut().zip(&before[from..from + len]) {
+            *entry = add(*entry, *previous);
+        }
+    }
+
+    /// Clamps `range` of cells along `axis` to the accumulated entries, returning the entries its sums start and end at.
+    fn clamp(&self, range: Range<u16>, axis: usize) -> (usize, usize) {
+        let (low, high) = self.bounds[axis];
+
+        (
+            (range.start as usize).clamp(low, high),
+            (range.end as usize).clamp(low, high),
+        )
+    }
+
+    /// The sums of the box covering the cells in `r`, `g` and `b`. Only valid after ['accumulate'](SummedVolume::accumulate).
+    pub fn of(&self, r: Range<u16>, g: Range<u16>, b: Range<u16>) -> [u64; N] {
+        if r.is_empty() || g.is_empty() || b.is_empty() {
+            return [0; N];
+        }
+
+        let (r0, r1) = self.clamp(r, 0);
+        let (g0, g1) = self.clamp(g, 1);
+        let (b0, b1) = self.clamp(b, 2);
+
+        let at = |i, j, k| self.entries[self.index_of(i, j, k)];
+
+        let added = [at(r1, g1, b1), at(r0, g0, b1), at(r0, g1, b0), at(r1, g0, b0)];
+        let subtracted = [at(r0, g1, b1), at(r1, g0, b1), at(r1, g1, b0), at(r0, g0, b0)];
+
+        let added = added.into_iter().fold([0; N], add);
+        let subtracted = subtracted.into_iter().fold([0; N], add);
+
+        sub(added, subtracted)
+    }
+}
+
+/// Adds each of the values, wrapping around.
+pub fn add<const N: usize>(a: [u64; N], b: [u64; N]) -> [u64; N] {
+    std::array::from_fn(|i| a[i].wrapping_add(b[i]))
+}
+
+/// Subtracts each of the values, wrapping around.
+fn sub<const N: usize>(a: [u64; N], b: [u64; N]) -> [u64; N] {
+    std::array::from_fn(|i| a[i].wrapping_sub(b[i]))
+}
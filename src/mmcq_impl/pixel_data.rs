@@ -0,0 +1,220 @@
+use super::{types::Pixel, PixelEncoding};
+
+/// The byte order of samples that take up more than one byte.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Least significant byte first.
+    LittleEndian,
+
+    /// Most significant byte first.
+    BigEndian,
+}
+
+/// How the color channels of floating point pixels are encoded. Alpha is always linear.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum TransferFunction {
+    /// Channels are linear light, as output by most renderers.
+    #[default]
+    Linear,
+
+    /// Channels are already encoded with the sRGB transfer function.
+    Srgb,
+}
+
+/// How floating point colors outside of the `0.0..=1.0` range are brought into it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ToneMapping {
+    /// Channels are clamped, so anything brighter than `1.0` becomes `1.0`.
+    #[default]
+    Clamp,
+
+    /// Channels are compressed with the Reinhard operator, `v / (1 + v)`, in linear light. Keeps some detail in highlights, at the cost of darkening the whole image.
+    Reinhard,
+}
+
+/// A borrowed buffer of pixels, where each channel of a pixel is a single sample.
+///
+/// How the channels of a pixel are laid out is described separately by ['PixelEncoding'](PixelEncoding). Slices of `u8` and `u16` convert into `PixelData` with `into()`, so they can be passed to ['PaletteExtractor'](crate::PaletteExtractor) directly.
+///
+/// # Examples
+/// ```
+/// use palette_extract::{ByteOrder, Color, PaletteExtractor, PixelData};
+///
+/// let extractor = PaletteExtractor::default();
+///
+/// // 16-bit red pixels, e.g. from a 16-bit PNG
+/// let pixels: [u16; 6] = [65535, 0, 0, 65535, 0, 0];
+/// assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(252, 4, 4));
+///
+/// // the same pixels as big endian bytes
+/// let bytes: [u8; 12] = [255, 255, 0, 0, 0, 0, 255, 255, 0, 0, 0, 0];
+/// let pixels = PixelData::U16Bytes(&bytes, ByteOrder::BigEndian);
+/// assert_eq!(extractor.dominant_color(pixels).unwrap(), Color::new(252, 4, 4));
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub enum PixelData<'a> {
+    /// 8 bits per channel.
+    U8(&'a [u8]),
+
+    /// 16 bits per channel.
+    U16(&'a [u16]),
+
+    /// 16 bits per channel, stored as pairs of bytes in the given byte order.
+    U16Bytes(&'a [u8], ByteOrder),
+
+    /// Floating point channels, nominally from `0.0` to `1.0`. How they're converted to sRGB colors is set with ['TransferFunction'](TransferFunction) and ['ToneMapping'](ToneMapping) on the extractor.
+    F32(&'a [f32]),
+}
+
+impl PixelData<'_> {
+    /// The length of the underlying slice.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            PixelData::U8(samples) => samples.len(),
+            PixelData::U16(samples) => samples.len(),
+            PixelData::U16Bytes(bytes, _) => bytes.len(),
+            PixelData::F32(samples) => samples.len(),
+        }
+    }
+
+    /// The number of elements of the underlying slice that represent an entire pixel.
+    pub(crate) fn stride(&self, encoding: PixelEncoding) -> u8 {
+        encoding.stride() * self.elements_per_sample() as u8
+    }
+
+    /// The number of elements of the underlying slice that represent a single channel.
+    pub(crate) fn elements_per_sample(&self) -> usize {
+        match self {
+            PixelData::U8(_) | PixelData::U16(_) | PixelData::F32(_) => 1,
+            PixelData::U16Bytes(_, _) => 2,
+        }
+    }
+
+    /// The number of bytes that represent a single channel.
+    pub(crate) fn bytes_per_sample(&self) -> usize {
+        match self {
+            PixelData::U8(_) => 1,
+            PixelData::U16(_) | PixelData::U16Bytes(_, _) => 2,
+            PixelData::F32(_) => 4,
+        }
+    }
+}
+
+/// Extracts the pixel starting at the `offset`th of floating point `samples`, decoding channels with `transfer_function` and `tone_mapping`.
+pub(crate) fn f32_pixel(
+    samples: &[f32],
+    encoding: PixelEncoding,
+    offset: usize,
+    transfer_function: TransferFunction,
+    tone_mapping: ToneMapping,
+) -> Pixel {
+    encoding.pixel_from(|c| {
+        let v = match encoding.alpha_channel() {
+            Some(a) if a != c && encoding.is_premultiplied() => {
+                // un-premultiply in the space the channels were premultiplied in, before
+                // converting them, and treat NaN or non-positive alpha as transparent
+                let alpha = samples[offset + a];
+                if alpha > 0.0 {
+                    samples[offset + c] / alpha
+                } else {
+                    0.0
+                }
+            }
+            _ => samples[offset + c],
+        };
+        if encoding.alpha_channel() == Some(c) {
+            scale_f32(v)
+        } else {
+            scale_f32(to_srgb(v, transfer_function, tone_mapping))
+        }
+    })
+}
+
+/// Converts a color channel to a tone mapped, sRGB encoded value from 0 to 1.
+fn to_srgb(v: f32, transfer_function: TransferFunction, tone_mapping: ToneMapping) -> f32 {
+    // NaN and negative light both become black
+    let v = v.max(0.0);
+
+    let linear = match transfer_function {
+        TransferFunction::Linear => v,
+        TransferFunction::Srgb if v <= 0.04045 => v / 12.92,
+        TransferFunction::Srgb => ((v + 0.055) / 1.055).powf(2.4),
+    };
+
+    let mapped = match tone_mapping {
+        ToneMapping::Clamp => linear.min(1.0),
+        ToneMapping::Reinhard if linear.is_infinite() => 1.0,
+        ToneMapping::Reinhard => linear / (1.0 + linear),
+    };
+
+    if mapped <= 0.0031308 {
+        mapped * 12.92
+    } else {
+        1.055 * mapped.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Scales a sample from 0 to 1 to the nearest 8-bit value, clamping anything outside of that range.
+fn scale_f32(v: f32) -> u8 {
+    // NaN saturates to 0 when cast
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Scales a 16-bit sample to the nearest 8-bit value.
+pub(crate) fn scale_u16(v: u16) -> u8 {
+    ((v as u32 * 255 + 32767) / 65535) as u8
+}
+
+impl<'a> From<&'a [u8]> for PixelData<'a> {
+    fn from(samples: &'a [u8]) -> Self {
+        PixelData::U8(samples)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for PixelData<'a> {
+    fn from(samples: &'a Vec<u8>) -> Self {
+        PixelData::U8(samples)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for PixelData<'a> {
+    fn from(samples: &'a [u8; N]) -> Self {
+        PixelData::U8(samples)
+    }
+}
+
+impl<'a> From<&'a [u16]> for PixelData<'a> {
+    fn from(samples: &'a [u16]) -> Self {
+        PixelData::U16(samples)
+    }
+}
+
+impl<'a> From<&'a Vec<u16>> for PixelData<'a> {
+    fn from(samples: &'a Vec<u16>) -> Self {
+        PixelData::U16(samples)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u16; N]> for PixelData<'a> {
+    fn from(samples: &'a [u16; N]) -> Self {
+        PixelData::U16(samples)
+    }
+}
+
+impl<'a> From<&'a [f32]> for PixelData<'a> {
+    fn from(samples: &'a [f32]) -> Self {
+        PixelData::F32(samples)
+    }
+}
+
+impl<'a> From<&'a Vec<f32>> for PixelData<'a> {
+    fn from(samples: &'a Vec<f32>) -> Self {
+        PixelData::F32(samples)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [f32; N]> for PixelData<'a> {
+    fn from(samples: &'a [f32; N]) -> Self {
+        PixelData::F32(samples)
+    }
+}
@@ -1,21 +1,80 @@
 use super::types::Pixel;
 
 /// Enum representing a pixel encoding.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PixelEncoding {
     /// RGB encoding
     Rgb,
 
     /// RGBA encoding
     Rgba,
+
+    /// BGR encoding
+    Bgr,
+
+    /// BGRA encoding, common for Windows capture APIs and video decoders.
+    Bgra,
+
+    /// ARGB encoding, in byte order (i.e. alpha is the first byte of each pixel).
+    Argb,
+
+    /// ABGR encoding, in byte order (i.e. alpha is the first byte of each pixel).
+    Abgr,
+
+    /// Grayscale encoding, a single luminance byte per pixel.
+    Gray,
+
+    /// Grayscale with alpha encoding, a luminance byte followed by an alpha byte.
+    GrayAlpha,
+
+    /// RGBA encoding, where the color channels are premultiplied by alpha, e.g. from compositors and Skia.
+    RgbaPremultiplied,
+
+    /// BGRA encoding, where the color channels are premultiplied by alpha.
+    BgraPremultiplied,
+
+    /// ARGB encoding in byte order, where the color channels are premultiplied by alpha.
+    ArgbPremultiplied,
+
+    /// ABGR encoding in byte order, where the color channels are premultiplied by alpha.
+    AbgrPremultiplied,
+
+    /// Grayscale with alpha encoding, where the luminance is premultiplied by alpha.
+    GrayAlphaPremultiplied,
 }
 
 impl PixelEncoding {
     /// Get the number of bytes that represent an entire pixel.
     pub fn stride(&self) -> u8 {
         match self {
-            PixelEncoding::Rgb => 3,
-            PixelEncoding::Rgba => 4,
+            PixelEncoding::Gray => 1,
+            PixelEncoding::GrayAlpha | PixelEncoding::GrayAlphaPremultiplied => 2,
+            PixelEncoding::Rgb | PixelEncoding::Bgr => 3,
+            PixelEncoding::Rgba
+            | PixelEncoding::Bgra
+            | PixelEncoding::Argb
+            | PixelEncoding::Abgr
+            | PixelEncoding::RgbaPremultiplied
+            | PixelEncoding::BgraPremultiplied
+            | PixelEncoding::ArgbPremultiplied
+            | PixelEncoding::AbgrPremultiplied => 4,
+        }
+    }
+
+    /// Whether the color channels are premultiplied by alpha.
+    pub fn is_premultiplied(&self) -> bool {
+        *self != self.straight()
+    }
+
+    /// Get the encoding with the same layout, but without premultiplied alpha.
+    pub(crate) fn straight(&self) -> PixelEncoding {
+        match self {
+            PixelEncoding::RgbaPremultiplied => PixelEncoding::Rgba,
+            PixelEncoding::BgraPremultiplied => PixelEncoding::Bgra,
+            PixelEncoding::ArgbPremultiplied => PixelEncoding::Argb,
+            PixelEncoding::AbgrPremultiplied => PixelEncoding::Abgr,
+            PixelEncoding::GrayAlphaPremultiplied => PixelEncoding::GrayAlpha,
+            encoding => *encoding,
         }
     }
 
@@ -28,19 +87,86 @@ impl PixelEncoding {
     /// # Returns
     /// Returns an extracted pixel for this particular encoding.
     pub fn extract_pixel(&self, buffer: &[u8], idx: usize) -> Pixel {
-        let stride = self.stride();
-        let offset = idx * stride as usize;
+        let offset = idx * self.stride() as usize;
+
+        self.pixel_from(|c| {
+            self.straight_channel(c, u8::MAX.into(), |c| buffer[offset + c].into()) as u8
+        })
+    }
 
+    /// Get the position of the alpha channel within a pixel, if there is one.
+    pub(crate) fn alpha_channel(&self) -> Option<usize> {
+        match self.straight() {
+            PixelEncoding::Rgb | PixelEncoding::Bgr | PixelEncoding::Gray => None,
+            PixelEncoding::Argb | PixelEncoding::Abgr => Some(0),
+            PixelEncoding::GrayAlpha => Some(1),
+            _ => Some(3),
+        }
+    }
+
+    /// Reads the `c`th channel of a pixel with `channel`, un-premultiplying it if needed. `max` is the value of a fully opaque alpha.
+    ///
+    /// Color channels of fully transparent premultiplied pixels are 0.
+    pub(crate) fn straight_channel(
+        &self,
+        c: usize,
+        max: u32,
+        channel: impl Fn(usize) -> u32,
+    ) -> u32 {
+        match self.alpha_channel() {
+            Some(a) if a != c && self.is_premultiplied() => {
+                unpremultiply(channel(c), channel(a), max)
+            }
+            _ => channel(c),
+        }
+    }
+
+    /// Get the positions of the red, green and blue channels within a pixel. Gray encodings repeat the gray channel.
+    pub(crate) fn color_channels(&self) -> [usize; 3] {
+        match self.straight() {
+            PixelEncoding::Rgb | PixelEncoding::Rgba => [0, 1, 2],
+            PixelEncoding::Bgr | PixelEncoding::Bgra => [2, 1, 0],
+            PixelEncoding::Argb => [1, 2, 3],
+            PixelEncoding::Abgr => [3, 2, 1],
+            _ => [0, 0, 0],
+        }
+    }
+
+    /// Builds a pixel from its channels, where `channel(c)` returns the `c`th channel of the pixel.
+    ///
+    /// Channels are used as they are, so premultiplied channels must already be un-premultiplied.
+    pub(crate) fn pixel_from(&self, channel: impl Fn(usize) -> u8) -> Pixel {
         match self {
-            PixelEncoding::Rgb => {
-                Pixel::new_rgb(buffer[offset], buffer[offset + 1], buffer[offset + 2])
+            PixelEncoding::Rgb => Pixel::new_rgb(channel(0), channel(1), channel(2)),
+            PixelEncoding::Rgba | PixelEncoding::RgbaPremultiplied => {
+                Pixel::new_rgba(channel(0), channel(1), channel(2), channel(3))
+            }
+            PixelEncoding::Bgr => Pixel::new_rgb(channel(2), channel(1), channel(0)),
+            PixelEncoding::Bgra | PixelEncoding::BgraPremultiplied => {
+                Pixel::new_rgba(channel(2), channel(1), channel(0), channel(3))
+            }
+            PixelEncoding::Argb | PixelEncoding::ArgbPremultiplied => {
+                Pixel::new_rgba(channel(1), channel(2), channel(3), channel(0))
+            }
+            PixelEncoding::Abgr | PixelEncoding::AbgrPremultiplied => {
+                Pixel::new_rgba(channel(3), channel(2), channel(1), channel(0))
+            }
+            PixelEncoding::Gray => {
+                let v = channel(0);
+                Pixel::new_rgb(v, v, v)
+            }
+            PixelEncoding::GrayAlpha | PixelEncoding::GrayAlphaPremultiplied => {
+                let v = channel(0);
+                Pixel::new_rgba(v, v, v, channel(1))
             }
-            PixelEncoding::Rgba => Pixel::new_rgba(
-                buffer[offset],
-                buffer[offset + 1],
-                buffer[offset + 2],
-                buffer[offset + 3],
-            ),
         }
     }
 }
+
+/// Un-premultiplies a `channel` by `alpha`, where `max` is the value of a fully opaque alpha. Fully transparent pixels are 0.
+pub(crate) fn unpremultiply(channel: u32, alpha: u32, max: u32) -> u32 {
+    match alpha {
+        0 => 0,
+        alpha => ((channel * max + alpha / 2) / alpha).min(max),
+    }
+}
@@ -0,0 +1,105 @@
+use super::config::WEIGHT_UNIT;
+
+/// A borrowed map with one entry per pixel of an image, saying how much each pixel counts towards the palette.
+///
+/// Attached to an ['ImageView'](crate::ImageView) with ['with_weights'](crate::ImageView::with_weights). Slices of `u8`, `bool` and `f32` convert into `PixelWeights` with `into()`.
+#[derive(Copy, Clone, Debug)]
+pub enum PixelWeights<'a> {
+    /// A mask where pixels with a nonzero entry are kept, and pixels with a zero entry are discarded.
+    Mask(&'a [u8]),
+
+    /// A mask where pixels with a `true` entry are kept, and pixels with a `false` entry are discarded.
+    BoolMask(&'a [bool]),
+
+    /// A weight from `0.0` to `1.0` for each pixel, where `1.0` counts as a whole pixel. Weights outside of that range are clamped, and `NaN` counts as `0.0`.
+    Weights(&'a [f32]),
+}
+
+impl PixelWeights<'_> {
+    /// The length of the underlying slice.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            PixelWeights::Mask(mask) => mask.len(),
+            PixelWeights::BoolMask(mask) => mask.len(),
+            PixelWeights::Weights(weights) => weights.len(),
+        }
+    }
+
+    /// The weight of a whole pixel, as counted in the histogram.
+    pub(crate) fn unit(&self) -> u64 {
+        match self {
+            PixelWeights::Mask(_) | PixelWeights::BoolMask(_) => 1,
+            PixelWeights::Weights(_) => WEIGHT_UNIT,
+        }
+    }
+
+    /// The weight of the entry at `idx`, in multiples of ['unit'](PixelWeights::unit).
+    pub(crate) fn weight(&self, idx: usize) -> u64 {
+        match self {
+            PixelWeights::Mask(mask) => (mask[idx] != 0).into(),
+            PixelWeights::BoolMask(mask) => mask[idx].into(),
+            PixelWeights::Weights(weights) => {
+                let w = weights[idx];
+                if w.is_nan() {
+                    0
+                } else {
+                    (w.clamp(0.0, 1.0) * WEIGHT_UNIT as f32).round() as u64
+                }
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a [u8]> for PixelWeights<'a> {
+    fn from(mask: &'a [u8]) -> Self {
+        PixelWeights::Mask(mask)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for PixelWeights<'a> {
+    fn from(mask: &'a Vec<u8>) -> Self {
+        PixelWeights::Mask(mask)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for PixelWeights<'a> {
+    fn from(mask: &'a [u8; N]) -> Self {
+        PixelWeights::Mask(mask)
+    }
+}
+
+impl<'a> From<&'a [bool]> for PixelWeights<'a> {
+    fn from(mask: &'a [bool]) -> Self {
+        PixelWeights::BoolMask(mask)
+    }
+}
+
+impl<'a> From<&'a Vec<bool>> for PixelWeights<'a> {
+    fn from(mask: &'a Vec<bool>) -> Self {
+        PixelWeights::BoolMask(mask)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [bool; N]> for PixelWeights<'a> {
+    fn from(mask: &'a [bool; N]) -> Self {
+        PixelWeights::BoolMask(mask)
+    }
+}
+
+impl<'a> From<&'a [f32]> for PixelWeights<'a> {
+    fn from(weights: &'a [f32]) -> Self {
+        PixelWeights::Weights(weights)
+    }
+}
+
+impl<'a> From<&'a Vec<f32>> for PixelWeights<'a> {
+    fn from(weights: &'a Vec<f32>) -> Self {
+        PixelWeights::Weights(weights)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [f32; N]> for PixelWeights<'a> {
+    fn from(weights: &'a [f32; N]) -> Self {
+        PixelWeights::Weights(weights)
+    }
+}
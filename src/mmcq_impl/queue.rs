@@ -0,0 +1,192 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::vbox::VBox;
+
+/// The order in which boxes are taken from a ['BoxQueue'](BoxQueue), most important last.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Priority {
+    /// By the number of pixels in the box.
+    Count,
+
+    /// By the number of pixels times the volume of the box, or by volume when the counts are equal.
+    Product,
+}
+
+impl Priority {
+    fn compare(self, a: &VBox, b: &VBox) -> Ordering {
+        match self {
+            Priority::Count => sort_by_count(a, b),
+            Priority::Product => sort_by_product(a, b),
+        }
+    }
+}
+
+fn sort_by_count(l: &VBox, r: &VBox) -> Ordering {
+    l.get_count().cmp(&r.get_count())
+}
+
+fn sort_by_product(a: &VBox, b: &VBox) -> Ordering {
+    let a_count = a.get_count();
+    let b_count = b.get_count();
+    let a_volume = a.get_volume();
+    let b_volume = b.get_volume();
+
+    if a_count == b_count {
+        // If count is 0 for both (or the same), sort by volume
+        a_volume.cmp(&b_volume)
+    } else {
+        // Otherwise sort by products
+        let a_product = a_count as u128 * a_volume as u128;
+        let b_product = b_count as u128 * b_volume as u128;
+        a_product.cmp(&b_product)
+    }
+}
+
+/// A box in the queue, along with the order it was pushed in.
+struct Queued {
+    vbox: VBox,
+    priority: Priority,
+    seq: u32,
+}
+
+impl Ord for Queued {
+    /// Boxes that compare equal are ordered by when they were pushed, the latest being the most important. That's the order a stable sort of the boxes would leave them in.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .compare(&self.vbox, &other.vbox)
+            .then(self.seq.cmp(&other.seq))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Queued {}
+
+/// A priority queue of boxes, from which the most important box is cut next.
+///
+/// Ties are broken by the order boxes were pushed in, so palettes don't depend on the implementation of the heap, and come out the same on every run and platform.
+pub struct BoxQueue {
+    heap: BinaryHeap<Queued>,
+    priority: Priority,
+    seq: u32,
+}
+
+impl BoxQueue {
+    pub fn new(priority: Priority) -> BoxQueue {
+        BoxQueue {
+            heap: BinaryHeap::new(),
+            priority,
+            seq: 0,
+        }
+    }
+
+    pub fn push(&mut self, vbox: VBox) {
+        self.heap.push(Queued {
+            vbox,
+            priority: self.priority,
+            seq: self.seq,
+        });
+        self.seq += 1;
+    }
+
+    /// Removes the most important box.
+    pub fn pop(&mut self) -> Option<VBox> {
+        self.heap.pop().map(|queued| queued.vbox)
+    }
+
+    /// The most important box.
+    pub fn peek(&self) -> Option<&VBox> {
+        self.heap.peek().map(|queued| &queued.vbox)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Moves the boxes into a new queue with a different priority. Boxes are pushed from least to most important, so ties keep their order.
+    pub fn reprioritize(self, priority: Priority) -> BoxQueue {
+        let mut queue = BoxQueue::new(priority);
+        for vbox in self.into_sorted_vec() {
+            queue.push(vbox);
+        }
+
+        queue
+    }
+
+    /// The boxes, from least to most important.
+    pub fn into_sorted_vec(self) -> Vec<VBox> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|queued| queued.vbox)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BoxQueue, Priority};
+    use crate::mmcq_impl::types::Color;
+    use crate::mmcq_impl::vbox::{test::synthetic_histogram, VBox};
+
+    fn colors(boxes: &[VBox]) -> Vec<Color> {
+        boxes.iter().map(|v| v.get_average()).collect()
+    }
+
+    #[test]
+    fn pops_the_most_important_box() {
+        let histogram = synthetic_histogram(&[((0, 0, 0), 1), ((1, 1, 1), 5), ((2, 2, 2), 3)]);
+
+        let mut queue = BoxQueue::new(Priority::Count);
+        for c in 0..3 {
+            queue.push(VBox::new(c, c, c, c, c, c, histogram.clone()));
+        }
+
+        let counts: Vec<u64> = std::iter::from_fn(|| queue.pop()).map(|v| v.get_count()).collect();
+        assert_eq!(counts, vec![5, 3, 1]);
+    }
+
+    #[test]
+    fn breaks_ties_by_push_order() {
+        let histogram = synthetic_histogram(&[((0, 0, 0), 2), ((1, 1, 1), 2), ((2, 2, 2), 2)]);
+
+        let mut queue = BoxQueue::new(Priority::Count);
+        for c in [1, 0, 2] {
+            queue.push(VBox::new(c, c, c, c, c, c, histogram.clone()));
+        }
+
+        // the latest box wins ties, like the last box after a stable sort
+        assert_eq!(
+            colors(&queue.into_sorted_vec()),
+            vec![Color::new(12, 12, 12), Color::new(4, 4, 4), Color::new(20, 20, 20)]
+        );
+    }
+
+    #[test]
+    fn reprioritize_keeps_the_order_of_ties() {
+        let histogram = synthetic_histogram(&[((0, 0, 0), 4), ((1, 1, 1), 2), ((2, 2, 2), 2)]);
+
+        let mut queue = BoxQueue::new(Priority::Count);
+        queue.push(VBox::new(2, 2, 2, 2, 2, 2, histogram.clone()));
+        queue.push(VBox::new(0, 0, 0, 0, 0, 0, histogram.clone()));
+        queue.push(VBox::new(1, 1, 1, 1, 1, 1, histogram.clone()));
+
+        let queue = queue.reprioritize(Priority::Product);
+
+        assert_eq!(
+            colors(&queue.into_sorted_vec()),
+            vec![Color::new(20, 20, 20), Color::new(12, 12, 12), Color::new(4, 4, 4)]
+        );
+    }
+}
@@ -43,35 +43,102 @@ impl Color {
     }
 }
 
+/// The bounds of the box of colors a ['Swatch'](Swatch) was quantized from.
+///
+/// Both bounds are inclusive. A box that covers no pixels can have a `min` channel greater than its `max` channel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorBox {
+    /// The lowest value of each color channel in the box.
+    pub min: Color,
+
+    /// The highest value of each color channel in the box.
+    pub max: Color,
+}
+
+/// A color from the extracted palette, along with how much of the image it represents.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Swatch {
+    /// The color of the swatch.
+    pub color: Color,
+
+    /// The number of sampled pixels that fall into this swatch. For weighted pixels, this is the sum of their weights, rounded to the nearest whole pixel.
+    ///
+    /// Boxes the median cut leaves without any pixels have a population of 0, and the midpoint of their bounds as their color. They come last in the palette.
+    pub population: u64,
+
+    /// The fraction of all sampled pixels that fall into this swatch, from 0 to 1.
+    pub proportion: f32,
+
+    /// The box of colors this swatch was quantized from.
+    pub bounds: ColorBox,
+}
+
 pub enum ColorChannel {
     R,
     G,
     B,
 }
 
-#[derive(Debug)]
+/// A sampled pixel, converted to 8-bit RGBA - the input to a ['PixelFilter'](crate::PixelFilter).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Pixel {
+    /// The red color channel.
     pub r: u8,
+
+    /// The green color channel.
     pub g: u8,
+
+    /// The blue color channel.
     pub b: u8,
+
+    /// The alpha channel. 255 for encodings without alpha.
     pub a: u8,
 }
 
 impl Pixel {
+    /// Creates a new Pixel struct.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use palette_extract::Pixel;
+    ///
+    /// Pixel::new_rgba(255, 255, 255, 128);
+    /// ```
     pub fn new_rgba(r: u8, g: u8, b: u8, a: u8) -> Pixel {
         Pixel { r, g, b, a }
     }
 
+    /// Creates a new, fully opaque Pixel struct.
     pub fn new_rgb(r: u8, g: u8, b: u8) -> Pixel {
         Pixel { r, g, b, a: 255 }
     }
 
-    pub fn is_white(&self) -> bool {
-        self.r > 250 && self.g > 250 && self.b > 250
+    /// Whether the red, green and blue channels are all above `threshold`. See ['PixelFilter::White'](crate::PixelFilter::White).
+    pub fn is_white(&self, threshold: u8) -> bool {
+        self.r > threshold && self.g > threshold && self.b > threshold
+    }
+
+    /// Whether the red, green and blue channels are all below `threshold`. See ['PixelFilter::Black'](crate::PixelFilter::Black).
+    pub fn is_black(&self, threshold: u8) -> bool {
+        self.r < threshold && self.g < threshold && self.b < threshold
     }
 
-    pub fn is_opaque(&self) -> bool {
-        self.a > 125
+    /// Whether the alpha channel is above `threshold`, i.e. the pixel is opaque enough to be sampled.
+    pub fn is_opaque(&self, threshold: u8) -> bool {
+        self.a > threshold
+    }
+
+    /// Blends the pixel over an opaque `background`, returning an opaque pixel.
+    pub(crate) fn composite_over(&self, background: Color) -> Pixel {
+        let a = self.a as u32;
+        let blend = |c: u8, bg: u8| ((c as u32 * a + bg as u32 * (255 - a) + 127) / 255) as u8;
+
+        Pixel::new_rgb(
+            blend(self.r, background.r),
+            blend(self.g, background.g),
+            blend(self.b, background.b),
+        )
     }
 }
 
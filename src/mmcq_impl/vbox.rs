@@ -1,58 +1,57 @@
 use std::cmp;
 use std::ops::Range;
 
-use super::config::MULTIPLIER;
-use super::util::color_index_from;
+use super::histogram::Histogram;
 
-use super::types::{Color, ColorChannel};
+use super::types::{Color, ColorBox, ColorChannel, Swatch};
 use std::rc::Rc;
 
 pub struct VBox {
-    r_min: u8,
-    r_max: u8,
-    g_min: u8,
-    g_max: u8,
-    b_min: u8,
-    b_max: u8,
-    pub histogram: Rc<Vec<u32>>,
-    pub volume: Option<u32>,
-    pub count: Option<u32>,
-    pub average: Option<Color>,
+    r_min: u16,
+    r_max: u16,
+    g_min: u16,
+    g_max: u16,
+    b_min: u16,
+    b_max: u16,
+    pub histogram: Rc<Histogram>,
+    volume: u32,
+    count: u64,
+    average: Color,
 }
 
 impl VBox {
-    pub fn r_range(&self) -> Range<u8> {
+    pub fn r_range(&self) -> Range<u16> {
         self.r_min..(self.r_max + 1)
     }
 
-    pub fn g_range(&self) -> Range<u8> {
+    pub fn g_range(&self) -> Range<u16> {
         self.g_min..(self.g_max + 1)
     }
 
-    pub fn b_range(&self) -> Range<u8> {
+    pub fn b_range(&self) -> Range<u16> {
         self.b_min..(self.b_max + 1)
     }
 
-    pub fn get_r_min(&self) -> u8 {
+    pub fn get_r_min(&self) -> u16 {
         self.r_min
     }
-    pub fn get_r_max(&self) -> u8 {
+    pub fn get_r_max(&self) -> u16 {
         self.r_max
     }
-    pub fn get_g_min(&self) -> u8 {
+    pub fn get_g_min(&self) -> u16 {
         self.g_min
     }
-    pub fn get_g_max(&self) -> u8 {
+    pub fn get_g_max(&self) -> u16 {
         self.g_max
     }
-    pub fn get_b_min(&self) -> u8 {
+    pub fn get_b_min(&self) -> u16 {
         self.b_min
     }
-    pub fn get_b_max(&self) -> u8 {
+    pub fn get_b_max(&self) -> u16 {
         self.b_max
     }
 
-    pub fn set_min(&mut self, v: u8, channel: &ColorChannel) {
+    pub fn set_min(&mut self, v: u16, channel: &ColorChannel) {
         match channel {
             ColorChannel::R => {
                 self.r_min = v;
@@ -65,12 +64,11 @@ impl VBox {
             }
         }
 
-        self.compute_average();
-        self.compute_count();
+        self.compute_moments();
         self.compute_new_volume();
     }
 
-    pub fn set_max(&mut self, v: u8, channel: &ColorChannel) {
+    pub fn set_max(&mut self, v: u16, channel: &ColorChannel) {
         match channel {
             ColorChannel::R => {
                 self.r_max = v;
@@ -83,19 +81,18 @@ impl VBox {
             }
         }
 
-        self.compute_average();
-        self.compute_count();
+        self.compute_moments();
         self.compute_new_volume();
     }
 
     pub fn new(
-        r_min: u8,
-        r_max: u8,
-        g_min: u8,
-        g_max: u8,
-        b_min: u8,
-        b_max: u8,
-        histogram: Rc<Vec<u32>>,
+        r_min: u16,
+        r_max: u16,
+        g_min: u16,
+        g_max: u16,
+        b_min: u16,
+        b_max: u16,
+        histogram: Rc<Histogram>,
     ) -> VBox {
         let mut n = VBox {
             r_min,
@@ -105,13 +102,12 @@ impl VBox {
             b_min,
             b_max,
             histogram,
-            volume: None,
-            count: None,
-            average: None,
+            volume: 0,
+            count: 0,
+            average: Color::new(0, 0, 0),
         };
 
-        n.compute_average();
-        n.compute_count();
+        n.compute_moments();
         n.compute_new_volume();
 
         n
@@ -132,115 +128,135 @@ impl VBox {
         }
     }
 
-    fn compute_count(&mut self) {
-        let mut count: u32 = 0;
-        for r in self.r_range() {
-            for g in self.g_range() {
-                for b in self.b_range() {
-                    let index = color_index_from(r, g, b);
-                    let value_at_index = self.histogram[index as usize];
-                    count = count + value_at_index as u32;
-                }
-            }
-        }
-
-        self.count = Some(count);
-    }
-
-    pub fn get_count(&self) -> u32 {
-        match self.count {
-            Some(v) => v,
-            None => {
-                panic!("Count not calced?")
-            }
-        }
+    pub fn get_count(&self) -> u64 {
+        self.count
     }
 
     fn compute_new_volume(&mut self) {
         let new_volume = (self.r_max + 1 - self.r_min) as u32
             * (self.g_max + 1 - self.g_min) as u32
             * (self.b_max + 1 - self.b_min) as u32;
-        self.volume = Some(new_volume);
+        self.volume = new_volume;
     }
 
     pub fn get_volume(&self) -> u32 {
-        match self.volume {
-            Some(v) => v,
-            None => {
-                panic!("Volume not yet computed?")
-            }
-        }
+        self.volume
     }
 
-    fn compute_average(&mut self) {
-        let mut ntot: u32 = 0;
-
-        let mut r_sum = 0;
-        let mut g_sum = 0;
-        let mut b_sum = 0;
-
-        for r in self.r_range() {
-            for g in self.g_range() {
-                for b in self.b_range() {
-                    let index = color_index_from(r, g, b);
-                    let hval = self.histogram[index as usize] as f32;
-                    ntot += hval as u32;
-                    r_sum += (hval * ((r as f32) + 0.5) * MULTIPLIER as f32) as u32;
-                    g_sum += (hval * ((g as f32) + 0.5) * MULTIPLIER as f32) as u32;
-                    b_sum += (hval * ((b as f32) + 0.5) * MULTIPLIER as f32) as u32;
-                }
-            }
+    /// Computes the count and average color of the box from the moments of the histogram.
+    fn compute_moments(&mut self) {
+        let moments = self.histogram.moments(self.r_range(), self.g_range(), self.b_range());
+        let ntot = moments.count;
+        let (mut r_sum, mut g_sum, mut b_sum) = (moments.r, moments.g, moments.b);
+
+        if self.histogram.averages_pixels() {
+            // round the means of the pixels to the nearest value
+            r_sum += ntot;
+            g_sum += ntot;
+            b_sum += ntot;
         }
 
-        let average = if ntot > 0 {
-            let r = (r_sum / ntot) as u8;
-            let g = (g_sum / ntot) as u8;
-            let b = (b_sum / ntot) as u8;
-            Color::new(r, g, b)
+        let average = if let (Some(r), Some(g), Some(b)) = (
+            r_sum.checked_div(2 * ntot),
+            g_sum.checked_div(2 * ntot),
+            b_sum.checked_div(2 * ntot),
+        ) {
+            Color::new(r as u8, g as u8, b as u8)
         } else {
-            let r =
-                (MULTIPLIER as f32 * (self.r_min + self.r_max + 1) as f32 / 2.0).min(255.0) as u8;
-            let g =
-                (MULTIPLIER as f32 * (self.g_min + self.g_max + 1) as f32 / 2.0).min(255.0) as u8;
-            let b =
-                (MULTIPLIER as f32 * (self.b_min + self.b_max + 1) as f32 / 2.0).min(255.0) as u8;
-            Color::new(r, g, b)
+            let multiplier = self.histogram.multiplier() as f32;
+            let midpoint =
+                |min: u16, max: u16| (multiplier * (min + max + 1) as f32 / 2.0).min(255.0) as u8;
+            Color::new(
+                midpoint(self.r_min, self.r_max),
+                midpoint(self.g_min, self.g_max),
+                midpoint(self.b_min, self.b_max),
+            )
         };
 
-        self.average = Some(average);
+        self.count = ntot;
+        self.average = average;
     }
 
     pub fn get_average(&self) -> Color {
-        match self.average {
-            Some(v) => v,
-            None => {
-                panic!("Average not set!")
-            }
+        self.average
+    }
+
+    pub fn get_bounds(&self) -> ColorBox {
+        let shift = self.histogram.shift();
+        let lower = |v: u16| cmp::min(v << shift, 255) as u8;
+        let upper = |v: u16| cmp::min(((v + 1) << shift) - 1, 255) as u8;
+
+        ColorBox {
+            min: Color::new(lower(self.r_min), lower(self.g_min), lower(self.b_min)),
+            max: Color::new(upper(self.r_max), upper(self.g_max), upper(self.b_max)),
+        }
+    }
+
+    /// Creates the swatch for this box, where `total` is the count of the whole histogram.
+    pub fn to_swatch(&self, total: u64) -> Swatch {
+        let unit = self.histogram.unit();
+
+        let proportion = if total > 0 {
+            (self.count as f64 / total as f64) as f32
+        } else {
+            0.0
+        };
+
+        Swatch {
+            color: self.get_average(),
+            population: (self.count + unit / 2) / unit,
+            proportion,
+            bounds: self.get_bounds(),
         }
     }
 
     pub fn widest_color_channel(&self) -> ColorChannel {
-        let r_width = self.r_max - self.r_min;
-        let g_width = self.g_max - self.g_min;
-        let b_width = self.b_max - self.b_min;
-
-        let m = cmp::max(cmp::max(r_width, g_width), b_width);
-        match m {
-            i if i == r_width => ColorChannel::R,
-            i if i == g_width => ColorChannel::G,
-            i if i == b_width => ColorChannel::B,
-            _ => {
-                panic!("shouldn't happen")
-            }
+        let r_width = self.r_max.saturating_sub(self.r_min);
+        let g_width = self.g_max.saturating_sub(self.g_min);
+        let b_width = self.b_max.saturating_sub(self.b_min);
+
+        if r_width >= g_width && r_width >= b_width {
+            ColorChannel::R
+        } else if g_width >= b_width {
+            ColorChannel::G
+        } else {
+            ColorChannel::B
         }
     }
 }
 
 #[cfg(test)]
-mod test {
-    use crate::mmcq_impl::histogram::create_histogram_and_vbox;
-    use crate::mmcq_impl::types::ColorChannel;
-    use crate::mmcq_impl::PixelEncoding;
+pub mod test {
+    use std::rc::Rc;
+
+    use super::VBox;
+    use crate::mmcq_impl::histogram::{create_histogram_and_vbox, Histogram};
+    use crate::mmcq_impl::types::{Color, ColorChannel, Pixel};
+    use crate::mmcq_impl::{ImageView, IntoImageView, PixelEncoding};
+    use crate::{ColorAverage, PaletteExtractor, PixelFilter, Quality};
+
+    /// Builds a histogram directly from `(bin, count)` pairs, so huge images don't need to be allocated.
+    pub fn synthetic_histogram(bins: &[((u8, u8, u8), u64)]) -> Rc<Histogram> {
+        let mut histogram = Histogram::new(5, 1, ColorAverage::BinCenter);
+        for &((r, g, b), count) in bins {
+            histogram.add(Pixel::new_rgb(r << 3, g << 3, b << 3), count);
+        }
+        histogram.build_moments([0; 3], [31; 3]);
+
+        Rc::new(histogram)
+    }
+
+    fn extractor() -> PaletteExtractor {
+        PaletteExtractor::builder()
+            .quality(Quality::new(1))
+            .pixel_filter(PixelFilter::default())
+            .build()
+            .unwrap()
+    }
+
+    fn view(pixels: &[u8]) -> ImageView<'_> {
+        pixels.into_image_view(PixelEncoding::Rgb).unwrap()
+    }
 
     fn create_pixels(dim: u32) -> Vec<u8> {
         let mut pixels: Vec<u8> = vec![];
@@ -257,7 +273,7 @@ mod test {
     fn gets_average() {
         let pixels = create_pixels(8);
 
-        let vbox = create_histogram_and_vbox(&pixels, PixelEncoding::Rgb, 1, true);
+        let vbox = create_histogram_and_vbox(&view(&pixels), &extractor()).unwrap();
 
         let color = vbox.get_average();
         assert_eq!(color.r, 252);
@@ -269,7 +285,7 @@ mod test {
     fn gets_average_min_max() {
         let pixels = create_pixels(8);
 
-        let mut vbox = create_histogram_and_vbox(&pixels, PixelEncoding::Rgb, 1, true);
+        let mut vbox = create_histogram_and_vbox(&view(&pixels), &extractor()).unwrap();
 
         vbox.set_max(31, &ColorChannel::R);
         vbox.set_min(32, &ColorChannel::R);
@@ -280,4 +296,27 @@ mod test {
         assert_eq!(color.g, 4);
         assert_eq!(color.b, 4);
     }
+
+    #[test]
+    fn counts_beyond_u32() {
+        let histogram = synthetic_histogram(&[((31, 0, 0), 10_000_000_000)]);
+
+        let vbox = VBox::new(31, 31, 0, 0, 0, 0, histogram);
+
+        assert_eq!(vbox.get_count(), 10_000_000_000);
+        assert_eq!(vbox.get_average(), Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn averages_beyond_u32() {
+        let histogram = synthetic_histogram(&[
+            ((31, 0, 0), 6_000_000_000),
+            ((0, 0, 31), 6_000_000_000),
+        ]);
+
+        let vbox = VBox::new(0, 31, 0, 0, 0, 31, histogram);
+
+        assert_eq!(vbox.get_count(), 12_000_000_000);
+        assert_eq!(vbox.get_average(), Color::new(128, 4, 128));
+    }
 }
@@ -0,0 +1,240 @@
+#![cfg(feature = "cli")]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use palette_extract::{Color, ImagePaletteExt, MaxColors, PaletteExtractor, PixelFilter, Quality};
+
+const LEAF: &str = "examples/test_images/leaf.jpg";
+const ZEBRA: &str = "examples/test_images/zebra.jpg";
+
+fn run(args: &[&str]) -> Output {
+    run_with_colorterm(args, None)
+}
+
+/// Runs the binary with `COLORTERM` set to `colorterm`, or unset.
+fn run_with_colorterm(args: &[&str], colorterm: Option<&str>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_palette-extract"));
+    command
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env_remove("NO_COLOR");
+    match colorterm {
+        Some(colorterm) => command.env("COLORTERM", colorterm),
+        None => command.env_remove("COLORTERM"),
+    };
+
+    command.output().unwrap()
+}
+
+fn stdout(args: &[&str]) -> String {
+    stdout_with_colorterm(args, None)
+}
+
+fn stdout_with_colorterm(args: &[&str], colorterm: Option<&str>) -> String {
+    let output = run_with_colorterm(args, colorterm);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Writes raw RGB pixels to a temporary file, which is removed once `f` returns.
+fn with_raw_file<T>(name: &str, pixels: &[u8], f: impl FnOnce(&str) -> T) -> T {
+    let file_name = format!("palette-extract-{}-{}", name, std::process::id());
+    let path = std::env::temp_dir().join(file_name);
+    std::fs::write(&path, pixels).unwrap();
+
+    let result = f(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+
+    result
+}
+
+fn expected_hex(path: &str, max_colors: u8, pixel_filter: PixelFilter) -> Vec<String> {
+    let extractor = PaletteExtractor::builder()
+        .quality(Quality::new(5))
+        .max_colors(MaxColors::new(max_colors))
+        .pixel_filter(pixel_filter)
+        .build()
+        .unwrap();
+    let img = image::open(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap();
+
+    img.palette(&extractor)
+        .unwrap()
+        .iter()
+        .map(|c| format!("#{:02X}{:02X}{:02X}", c.r, c.g, c.b))
+        .collect()
+}
+
+#[test]
+fn hex() {
+    let out = stdout(&[LEAF, "--max-colors", "4"]);
+
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        expected_hex(LEAF, 4, PixelFilter::default())
+    );
+}
+
+#[test]
+fn hex_with_options() {
+    let out = stdout(&[ZEBRA, "-m", "6", "-q", "5", "--filter", "black=40"]);
+
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        expected_hex(ZEBRA, 6, PixelFilter::Black { threshold: 40 })
+    );
+}
+
+#[test]
+fn hex_multiple_images() {
+    let out = stdout(&[LEAF, ZEBRA, "-m", "3", "--filter", "none"]);
+
+    let mut expected = vec![format!("{}:", LEAF)];
+    expected.extend(expected_hex(LEAF, 3, PixelFilter::None));
+    expected.push(String::new());
+    expected.push(format!("{}:", ZEBRA));
+    expected.extend(expected_hex(ZEBRA, 3, PixelFilter::None));
+
+    assert_eq!(out.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn json() {
+    let out = stdout(&[LEAF, ZEBRA, "--format", "json"]);
+
+    assert!(out.starts_with("[\n"));
+    assert!(out.ends_with("]\n"));
+    assert!(out.contains(&format!("\"path\": \"{}\"", LEAF)));
+    assert!(out.contains(&format!("\"path\": \"{}\"", ZEBRA)));
+
+    let colors = expected_hex(LEAF, 10, PixelFilter::default()).len()
+        + expected_hex(ZEBRA, 10, PixelFilter::default()).len();
+    assert_eq!(out.matches("\"hex\": \"#").count(), colors);
+    assert_eq!(out.matches("\"population\": ").count(), colors);
+}
+
+#[test]
+fn csv() {
+    let out = stdout(&[LEAF, "--format", "csv", "-m", "4"]);
+    let mut lines = out.lines();
+
+    assert_eq!(lines.next(), Some("path,hex,r,g,b,population,proportion"));
+
+    let rows: Vec<Vec<&str>> = lines.map(|l| l.split(',').collect()).collect();
+    let hexes: Vec<String> = rows.iter().map(|r| r[1].to_string()).collect();
+    assert_eq!(hexes, expected_hex(LEAF, 4, PixelFilter::default()));
+
+    for row in &rows {
+        assert_eq!(row.len(), 7);
+        assert_eq!(row[0], LEAF);
+        let channel = |i: usize| row[i].parse::<u8>().unwrap();
+        assert_eq!(row[1], format!("#{:02X}{:02X}{:02X}", channel(2), channel(3), channel(4)));
+    }
+
+    let proportion: f32 = rows.iter().map(|r| r[6].parse::<f32>().unwrap()).sum();
+    assert!((proportion - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn raw_pixels() {
+    let pixels = [0u8, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255, 255];
+
+    let (bgra, rgba) = with_raw_file("raw", &pixels, |path| {
+        (stdout(&[path, "--encoding", "bgra"]), stdout(&[path, "--encoding", "rgba"]))
+    });
+
+    assert_eq!(bgra, "#FC0404\n");
+    assert_eq!(rgba, "#0404FC\n");
+}
+
+#[test]
+fn missing_file() {
+    let output = run(&["does/not/exist.png", LEAF, "-m", "2"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does/not/exist.png"));
+
+    // the other images are still extracted
+    let out = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        expected_hex(LEAF, 2, PixelFilter::default())
+    );
+}
+
+#[test]
+fn invalid_arguments() {
+    for args in [
+        &[LEAF, "--quality", "0"][..],
+        &[LEAF, "--max-colors", "0"],
+        &[LEAF, "--filter", "pink"],
+        &[LEAF, "--filter", "white=256"],
+        &[LEAF, "--format", "xml"],
+        &[LEAF, "--encoding", "cmyk"],
+        &[LEAF, "--color", "sometimes"],
+        &[],
+    ] {
+        assert_eq!(run(args).status.code(), Some(2), "{:?}", args);
+    }
+}
+
+#[test]
+fn truecolor_preview() {
+    let out = stdout_with_colorterm(&[LEAF, "-m", "4", "--color", "always"], Some("truecolor"));
+
+    let extractor = PaletteExtractor::builder()
+        .max_colors(MaxColors::new(4))
+        .build()
+        .unwrap();
+    let img = image::open(Path::new(env!("CARGO_MANIFEST_DIR")).join(LEAF)).unwrap();
+    let expected: Vec<String> = img
+        .swatches(&extractor)
+        .unwrap()
+        .iter()
+        .map(|s| {
+            let Color { r, g, b } = s.color;
+            format!(
+                "\x1b[48;2;{};{};{}m      \x1b[0m #{:02X}{:02X}{:02X} {}",
+                r, g, b, r, g, b, s.population
+            )
+        })
+        .collect();
+
+    assert_eq!(out.lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn ansi256_preview() {
+    let pixels = [255u8, 0, 0, 255, 0, 0, 100, 100, 100];
+
+    for colorterm in [None, Some("yes")] {
+        let out = with_raw_file("ansi256", &pixels, |path| {
+            let args = [path, "--encoding", "rgb", "-q", "1", "-m", "2", "--color", "always"];
+            stdout_with_colorterm(&args, colorterm)
+        });
+
+        // bright red is in the color cube, and dark gray is on the grayscale ramp
+        let lines: Vec<&str> = out.lines().collect();
+        assert!(lines.contains(&"\x1b[48;5;196m      \x1b[0m #FC0404 2"), "{:?}", lines);
+        assert!(lines.contains(&"\x1b[48;5;241m      \x1b[0m #646464 1"), "{:?}", lines);
+    }
+}
+
+#[test]
+fn no_preview() {
+    let plain = expected_hex(LEAF, 4, PixelFilter::default()).join("\n") + "\n";
+
+    // stdout isn't a terminal here, so auto doesn't preview
+    assert_eq!(stdout_with_colorterm(&[LEAF, "-m", "4"], Some("truecolor")), plain);
+    assert_eq!(
+        stdout_with_colorterm(&[LEAF, "-m", "4", "--color", "never"], Some("truecolor")),
+        plain
+    );
+
+    // only hex output is previewed
+    for format in ["json", "csv"] {
+        let args = [LEAF, "--format", format, "--color", "always"];
+        assert!(!stdout_with_colorterm(&args, Some("truecolor")).contains('\x1b'));
+    }
+}
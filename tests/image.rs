@@ -0,0 +1,147 @@
+#![cfg(feature = "image")]
+
+use image::{
+    DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, Rgb32FImage,
+    RgbImage, Rgba, Rgba32FImage, RgbaImage,
+};
+use palette_extract::{
+    Color, ImagePaletteExt, ImageView, MaxColors, PaletteError, PaletteExtractor, PixelEncoding, PixelFilter,
+    Quality, TransferFunction,
+};
+
+fn extractor() -> PaletteExtractor {
+    PaletteExtractor::builder()
+        // ignored, images are read with the encoding of their color type
+        .encoding(PixelEncoding::Bgr)
+        .quality(Quality::new(1))
+        .max_colors(MaxColors::new(4))
+        .pixel_filter(PixelFilter::None)
+        .build()
+        .unwrap()
+}
+
+/// A 16x16 image, 3/4 red at the top and blue at the bottom.
+fn rgb_image() -> RgbImage {
+    RgbImage::from_fn(16, 16, |_, y| if y < 12 { Rgb([255, 0, 0]) } else { Rgb([0, 0, 255]) })
+}
+
+/// The palette of `rgb_image`, read as raw RGB bytes.
+fn expected() -> Vec<Color> {
+    let raw = rgb_image().into_raw();
+    let view = ImageView::new(&raw, PixelEncoding::Rgb, 16, 16).unwrap();
+
+    extractor().extract(view).unwrap()
+}
+
+const RED: Color = Color { r: 252, g: 4, b: 4 };
+
+#[test]
+fn dynamic_images() {
+    let rgb = DynamicImage::ImageRgb8(rgb_image());
+    let expected = expected();
+
+    for img in [
+        rgb.clone(),
+        DynamicImage::ImageRgba8(rgb.to_rgba8()),
+        DynamicImage::ImageRgb16(rgb.to_rgb16()),
+        DynamicImage::ImageRgba16(rgb.to_rgba16()),
+        DynamicImage::ImageRgb32F(rgb.to_rgb32f()),
+        DynamicImage::ImageRgba32F(rgb.to_rgba32f()),
+    ] {
+        assert_eq!(img.palette(&extractor()).unwrap(), expected, "{:?}", img.color());
+        assert_eq!(img.dominant_color(&extractor()).unwrap(), RED);
+    }
+}
+
+#[test]
+fn dynamic_gray_images() {
+    let gray = DynamicImage::ImageLuma8(GrayImage::from_fn(4, 4, |x, _| Luma([x as u8 * 60])));
+
+    let view = ImageView::new(gray.as_bytes(), PixelEncoding::Gray, 4, 4).unwrap();
+    let expected = extractor().extract_swatches(view).unwrap();
+
+    for img in [
+        gray.clone(),
+        DynamicImage::ImageLumaA8(gray.to_luma_alpha8()),
+        DynamicImage::ImageLuma16(gray.to_luma16()),
+        DynamicImage::ImageLumaA16(gray.to_luma_alpha16()),
+    ] {
+        assert_eq!(img.swatches(&extractor()).unwrap(), expected, "{:?}", img.color());
+    }
+}
+
+#[test]
+fn image_buffers() {
+    let rgb = rgb_image();
+    let expected = expected();
+
+    let rgba: RgbaImage = DynamicImage::ImageRgb8(rgb.clone()).to_rgba8();
+    let rgb16: ImageBuffer<Rgb<u16>, Vec<u16>> = DynamicImage::ImageRgb8(rgb.clone()).to_rgb16();
+    let rgba32f: Rgba32FImage = DynamicImage::ImageRgb8(rgb.clone()).to_rgba32f();
+    let rgb32f: Rgb32FImage = DynamicImage::ImageRgb8(rgb.clone()).to_rgb32f();
+
+    assert_eq!(extractor().extract(&rgb).unwrap(), expected);
+    assert_eq!(extractor().extract(&rgba).unwrap(), expected);
+    assert_eq!(extractor().extract(&rgb16).unwrap(), expected);
+    assert_eq!(extractor().extract(&rgba32f).unwrap(), expected);
+    assert_eq!(rgb32f.palette(&extractor()).unwrap(), expected);
+    assert_eq!(rgba.dominant_color(&extractor()).unwrap(), RED);
+}
+
+#[test]
+fn float_images_are_srgb() {
+    let mid_tone = DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([128, 64, 32])));
+    let expected = mid_tone.palette(&extractor()).unwrap();
+    assert_eq!(expected[0], Color::new(132, 68, 36));
+
+    // the extractor's transfer function doesn't apply to images of the `image` crate
+    let linear = PaletteExtractor::builder()
+        .quality(Quality::new(1))
+        .max_colors(MaxColors::new(4))
+        .pixel_filter(PixelFilter::None)
+        .transfer_function(TransferFunction::Linear)
+        .build()
+        .unwrap();
+
+    for img in [
+        DynamicImage::ImageRgb16(mid_tone.to_rgb16()),
+        DynamicImage::ImageRgba16(mid_tone.to_rgba16()),
+        DynamicImage::ImageRgb32F(mid_tone.to_rgb32f()),
+        DynamicImage::ImageRgba32F(mid_tone.to_rgba32f()),
+    ] {
+        assert_eq!(img.palette(&linear).unwrap(), expected, "{:?}", img.color());
+    }
+
+    assert_eq!(linear.extract(&mid_tone.to_rgb16()).unwrap(), expected);
+    assert_eq!(linear.extract(&mid_tone.to_rgb32f()).unwrap(), expected);
+    assert_eq!(linear.extract(&mid_tone.to_rgba32f()).unwrap(), expected);
+}
+
+#[test]
+fn alpha() {
+    let img = GrayAlphaImage::from_fn(4, 4, |x, _| {
+        if x == 0 { LumaA([200, 255]) } else { LumaA([20, 0]) }
+    });
+
+    let swatches = img.swatches(&extractor()).unwrap();
+    assert_eq!(swatches[0].color, Color::new(204, 204, 204));
+    assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 4);
+
+    let transparent = ImageBuffer::from_pixel(2, 2, Rgba([255u8, 0, 0, 0]));
+    assert_eq!(transparent.palette(&extractor()), Err(PaletteError::NoPixels));
+}
+
+#[test]
+fn borrowed_buffers() {
+    let raw: Vec<u8> = rgb_image().into_raw();
+    let borrowed: ImageBuffer<Rgb<u8>, &[u8]> = ImageBuffer::from_raw(16, 16, &raw[..]).unwrap();
+
+    assert_eq!(borrowed.dominant_color(&extractor()).unwrap(), RED);
+}
+
+#[test]
+fn empty_image() {
+    let img = DynamicImage::new_rgb8(0, 0);
+
+    assert_eq!(img.palette(&extractor()), Err(PaletteError::EmptyInput));
+}
@@ -1,3 +1,6 @@
+#[cfg(test)]
+use palette_extract::{MaxColors, PaletteExtractor, PaletteExtractorBuilder, PixelFilter, Quality};
+
 #[cfg(test)]
 fn create_image(img_size: u32, get_pixel: fn(u32, u32) -> (u8, u8, u8)) -> Vec<u8> {
     let mut pixels: Vec<u8> = Vec::with_capacity(img_size as usize * 3);
@@ -13,6 +16,39 @@ fn create_image(img_size: u32, get_pixel: fn(u32, u32) -> (u8, u8, u8)) -> Vec<u
     pixels
 }
 
+/// A 64x64 image with a red stripe, and the rest split between blue and green.
+#[cfg(test)]
+fn three_color_image() -> Vec<u8> {
+    create_image(64, |x, y| {
+        if x < 16 {
+            (200, 40, 10)
+        } else if y < 40 {
+            (10, 90, 220)
+        } else {
+            (30, 200, 60)
+        }
+    })
+}
+
+/// An extractor that samples every pixel, filters none of them, and returns up to 4 colors.
+#[cfg(test)]
+fn extractor() -> PaletteExtractor {
+    extractor_with(|builder| builder)
+}
+
+/// An extractor like [`extractor`], with `options` set on top.
+#[cfg(test)]
+fn extractor_with(
+    options: impl FnOnce(PaletteExtractorBuilder) -> PaletteExtractorBuilder,
+) -> PaletteExtractor {
+    let builder = PaletteExtractor::builder()
+        .quality(Quality::new(1))
+        .max_colors(MaxColors::new(4))
+        .pixel_filter(PixelFilter::None);
+
+    options(builder).build().unwrap()
+}
+
 #[cfg(test)]
 mod get_palette_with_options {
     use super::create_image;
@@ -68,6 +104,7 @@ mod get_palette_with_options {
     }
 
     #[test]
+    #[allow(clippy::needless_return, clippy::manual_range_contains)]
     fn solids() {
         let pixels = create_image(512, |x, _| {
             if x < 128 {
@@ -118,3 +155,1701 @@ mod get_palette_rgb {
         assert_eq!(r[0], Color::new(252, 4, 4));
     }
 }
+
+#[cfg(test)]
+mod try_get_palette_with_options {
+    use super::create_image;
+    use palette_extract::{
+        try_get_palette_with_options, Color, MaxColors, PaletteError, PixelEncoding, PixelFilter,
+        Quality,
+    };
+
+    fn try_rgb(pixels: &[u8], quality: u8, max_colors: u8) -> Result<Vec<Color>, PaletteError> {
+        try_get_palette_with_options(
+            pixels,
+            PixelEncoding::Rgb,
+            Quality::new(quality),
+            MaxColors::new(max_colors),
+            PixelFilter::default(),
+        )
+    }
+
+    #[test]
+    fn red_rgb() {
+        let pixels = create_image(280, |_, _| (255, 0, 0));
+
+        let r = try_rgb(&pixels, 1, 3).unwrap();
+
+        assert_eq!(r[0], Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(try_rgb(&[], 1, 3), Err(PaletteError::EmptyInput));
+    }
+
+    #[test]
+    fn invalid_buffer_length() {
+        let pixels = [255, 0, 0, 255, 0];
+
+        assert_eq!(
+            try_rgb(&pixels, 1, 3),
+            Err(PaletteError::InvalidBufferLength { len: 5, stride: 3 })
+        );
+    }
+
+    #[test]
+    fn zero_quality() {
+        let pixels = create_image(4, |_, _| (255, 0, 0));
+
+        assert_eq!(try_rgb(&pixels, 0, 3), Err(PaletteError::ZeroQuality));
+    }
+
+    #[test]
+    fn zero_max_colors() {
+        let pixels = create_image(4, |_, _| (255, 0, 0));
+
+        assert_eq!(try_rgb(&pixels, 1, 0), Err(PaletteError::ZeroMaxColors));
+    }
+
+    #[test]
+    fn one_max_color() {
+        let pixels = create_image(64, |x, _| if x < 32 { (255, 0, 0) } else { (0, 0, 255) });
+
+        assert!(!try_rgb(&pixels, 1, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn all_white_filtered() {
+        let pixels = create_image(4, |_, _| (255, 255, 255));
+
+        assert_eq!(try_rgb(&pixels, 1, 3), Err(PaletteError::NoPixels));
+    }
+
+    #[test]
+    fn all_transparent() {
+        let pixels = [255, 0, 0, 0, 0, 255, 0, 100];
+
+        let r = try_get_palette_with_options(
+            &pixels,
+            PixelEncoding::Rgba,
+            Quality::new(1),
+            MaxColors::new(3),
+            PixelFilter::None,
+        );
+
+        assert_eq!(r, Err(PaletteError::NoPixels));
+    }
+}
+
+#[cfg(test)]
+mod palette_extractor {
+    use super::create_image;
+    use palette_extract::{
+        get_palette_with_options, Color, MaxColors, PaletteError, PaletteExtractor, PixelEncoding,
+        PixelFilter, Quality,
+    };
+
+    #[test]
+    fn matches_get_palette_with_options() {
+        let pixels = create_image(512, |x, _| {
+            if x < 128 {
+                (0, 0, 255)
+            } else if x < 480 {
+                (0, 255, 0)
+            } else {
+                (255, 0, 0)
+            }
+        });
+
+        let extractor = PaletteExtractor::builder()
+            .encoding(PixelEncoding::Rgb)
+            .quality(Quality::new(1))
+            .max_colors(MaxColors::new(4))
+            .pixel_filter(PixelFilter::White { threshold: 250 })
+            .build()
+            .unwrap();
+
+        let expected = get_palette_with_options(
+            &pixels,
+            PixelEncoding::Rgb,
+            Quality::new(1),
+            MaxColors::new(4),
+            PixelFilter::White { threshold: 250 },
+        );
+
+        assert_eq!(extractor.extract(&pixels).unwrap(), expected);
+    }
+
+    #[test]
+    fn validates_on_build() {
+        let zero_quality = PaletteExtractor::builder().quality(Quality::new(0)).build();
+        let zero_max_colors = PaletteExtractor::builder()
+            .max_colors(MaxColors::new(0))
+            .build();
+
+        assert_eq!(zero_quality.unwrap_err(), PaletteError::ZeroQuality);
+        assert_eq!(zero_max_colors.unwrap_err(), PaletteError::ZeroMaxColors);
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let extractor = PaletteExtractor::builder()
+            .quality(Quality::new(1))
+            .build()
+            .unwrap();
+        let pixels = create_image(32, |_, _| (255, 0, 0));
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| extractor.extract(&pixels).unwrap()))
+                .collect();
+
+            for handle in handles {
+                assert_eq!(handle.join().unwrap()[0], Color::new(252, 4, 4));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod extract_swatches {
+    use super::{create_image, extractor};
+    use palette_extract::{Color, ColorBox};
+
+    #[test]
+    fn populations() {
+        let pixels = create_image(512, |x, _| {
+            if x < 128 {
+                (0, 0, 255)
+            } else if x < 480 {
+                (0, 255, 0)
+            } else {
+                (255, 0, 0)
+            }
+        });
+
+        let swatches = extractor().extract_swatches(&pixels).unwrap();
+
+        let green = swatches
+            .iter()
+            .find(|s| s.color == Color::new(4, 252, 4))
+            .unwrap();
+        assert_eq!(green.population, 352 * 512);
+        assert_eq!(green.proportion, 352.0 / 512.0);
+
+        let population: u64 = swatches.iter().map(|s| s.population).sum();
+        let proportion: f32 = swatches.iter().map(|s| s.proportion).sum();
+        assert_eq!(population, 512 * 512);
+        assert!((proportion - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn same_order_as_extract() {
+        let pixels = create_image(64, |x, y| ((x * 4) as u8, (y * 4) as u8, 128));
+
+        let extractor = extractor();
+        let colors = extractor.extract(&pixels).unwrap();
+        let swatches = extractor.extract_swatches(&pixels).unwrap();
+
+        let swatch_colors: Vec<Color> = swatches.iter().map(|s| s.color).collect();
+        assert_eq!(swatch_colors, colors);
+    }
+
+    #[test]
+    fn bounds() {
+        let pixels = create_image(16, |_, _| (255, 0, 0));
+
+        let swatches = extractor().extract_swatches(&pixels).unwrap();
+
+        assert_eq!(swatches[0].bounds.min, Color::new(248, 0, 0));
+        assert_eq!(swatches[0].bounds.max, Color::new(255, 7, 7));
+    }
+
+    #[test]
+    fn empty_boxes() {
+        let pixels = create_image(8, |x, _| if x < 6 { (255, 0, 0) } else { (0, 0, 255) });
+
+        let swatches = extractor().extract_swatches(&pixels).unwrap();
+        let populations: Vec<u64> = swatches.iter().map(|s| s.population).collect();
+
+        // two colors, and the two boxes the median cut left empty at the end
+        assert_eq!(populations, vec![16, 48, 0, 0]);
+        assert_eq!(swatches[0].color, Color::new(4, 4, 252));
+        assert_eq!(swatches[1].color, Color::new(252, 4, 4));
+
+        for swatch in &swatches[2..] {
+            let ColorBox { min, max } = swatch.bounds;
+            let midpoint = |min: u8, max: u8| (min as u16 + max as u16).div_ceil(2).min(255) as u8;
+
+            assert_eq!(swatch.proportion, 0.0);
+            assert_eq!(
+                swatch.color,
+                Color::new(midpoint(min.r, max.r), midpoint(min.g, max.g), midpoint(min.b, max.b))
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod dominant_color {
+    use super::create_image;
+    use palette_extract::{
+        get_dominant_color, get_palette_with_options, try_get_dominant_color, Color, MaxColors,
+        PaletteError, PaletteExtractor, PixelEncoding, PixelFilter, Quality,
+    };
+
+    #[test]
+    fn most_populous_not_first_in_palette() {
+        let pixels = create_image(512, |x, _| {
+            if x < 128 {
+                (0, 0, 255)
+            } else if x < 480 {
+                (0, 255, 0)
+            } else {
+                (255, 0, 0)
+            }
+        });
+
+        let palette = get_palette_with_options(
+            &pixels,
+            PixelEncoding::Rgb,
+            Quality::new(1),
+            MaxColors::new(4),
+            PixelFilter::None,
+        );
+        let extractor = PaletteExtractor::builder()
+            .quality(Quality::new(1))
+            .build()
+            .unwrap();
+        let dominant = extractor.dominant_swatch(&pixels).unwrap();
+
+        assert_eq!(palette[0], Color::new(4, 4, 252));
+        assert_eq!(dominant.color, Color::new(4, 252, 4));
+        assert_eq!(dominant.population, 352 * 512);
+    }
+
+    #[test]
+    fn largest_cluster_not_densest_cell() {
+        // 24 reds spread one per histogram cell, and 4 blues in a single cell
+        let mut pixels: Vec<u8> = (0..24u8).flat_map(|i| [64 + 8 * i, 0, 0]).collect();
+        pixels.extend([0u8, 0, 255].repeat(4));
+
+        let extractor = PaletteExtractor::builder()
+            .quality(Quality::new(1))
+            .pixel_filter(PixelFilter::None)
+            .build()
+            .unwrap();
+        let dominant = extractor.dominant_swatch(&pixels).unwrap();
+
+        // the blue cell is the densest, but the reds are cut into boxes of 6
+        assert_eq!(dominant.color, Color::new(128, 4, 4));
+        assert_eq!(dominant.population, 6);
+    }
+
+    #[test]
+    fn defaults() {
+        let pixels = create_image(280, |x, _| if x < 100 { (255, 255, 255) } else { (255, 0, 0) });
+
+        assert_eq!(get_dominant_color(&pixels), Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(try_get_dominant_color(&[]), Err(PaletteError::EmptyInput));
+    }
+}
+
+#[cfg(test)]
+mod pixel_encoding {
+    use super::three_color_image;
+    use palette_extract::{
+        get_palette_with_options, Color, MaxColors, PixelEncoding, PixelFilter, Quality,
+    };
+
+    fn palette(pixels: &[u8], encoding: PixelEncoding) -> Vec<Color> {
+        get_palette_with_options(
+            pixels,
+            encoding,
+            Quality::new(1),
+            MaxColors::new(4),
+            PixelFilter::None,
+        )
+    }
+
+    /// Re-encodes RGB pixels, `order` giving the source channel (0-2 for RGB, 3 for alpha) of each output byte.
+    fn reorder(pixels: &[u8], order: &[usize]) -> Vec<u8> {
+        pixels
+            .chunks(3)
+            .flat_map(|p| order.iter().map(move |&c| if c == 3 { 255 } else { p[c] }))
+            .collect()
+    }
+
+    #[test]
+    fn bgr() {
+        let pixels = three_color_image();
+
+        assert_eq!(
+            palette(&reorder(&pixels, &[2, 1, 0]), PixelEncoding::Bgr),
+            palette(&pixels, PixelEncoding::Rgb)
+        );
+    }
+
+    #[test]
+    fn bgra() {
+        let pixels = three_color_image();
+
+        assert_eq!(
+            palette(&reorder(&pixels, &[2, 1, 0, 3]), PixelEncoding::Bgra),
+            palette(&pixels, PixelEncoding::Rgb)
+        );
+    }
+
+    #[test]
+    fn argb() {
+        let pixels = three_color_image();
+
+        assert_eq!(
+            palette(&reorder(&pixels, &[3, 0, 1, 2]), PixelEncoding::Argb),
+            palette(&pixels, PixelEncoding::Rgb)
+        );
+    }
+
+    #[test]
+    fn abgr() {
+        let pixels = three_color_image();
+
+        assert_eq!(
+            palette(&reorder(&pixels, &[3, 2, 1, 0]), PixelEncoding::Abgr),
+            palette(&pixels, PixelEncoding::Rgb)
+        );
+    }
+
+    #[test]
+    fn alpha_position() {
+        // a single opaque red pixel next to a transparent green one
+        let argb = [255, 255, 0, 0, 0, 0, 255, 0];
+
+        assert_eq!(palette(&argb, PixelEncoding::Argb)[0], Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn gray() {
+        let pixels: Vec<u8> = three_color_image().chunks(3).map(|p| p[0]).collect();
+        let rgb: Vec<u8> = pixels.iter().flat_map(|&v| [v, v, v]).collect();
+
+        assert_eq!(
+            palette(&pixels, PixelEncoding::Gray),
+            palette(&rgb, PixelEncoding::Rgb)
+        );
+    }
+
+    #[test]
+    fn gray_alpha() {
+        // opaque dark gray pixels, plus transparent light ones that should be ignored
+        let pixels: Vec<u8> = (0..64)
+            .flat_map(|i| if i % 2 == 0 { [40, 255] } else { [220, 0] })
+            .collect();
+
+        let r = palette(&pixels, PixelEncoding::GrayAlpha);
+
+        assert_eq!(r[0], Color::new(44, 44, 44));
+    }
+}
+
+#[cfg(test)]
+mod sixteen_bit {
+    use super::{extractor_with, three_color_image};
+    use palette_extract::{ByteOrder, Color, PixelData, PixelEncoding};
+
+    fn widen(pixels: &[u8]) -> Vec<u16> {
+        pixels.iter().map(|&v| v as u16 * 257).collect()
+    }
+
+    #[test]
+    fn rgb16() {
+        let pixels = three_color_image();
+        let extractor = extractor_with(|builder| builder.encoding(PixelEncoding::Rgb));
+
+        assert_eq!(
+            extractor.extract(&widen(&pixels)).unwrap(),
+            extractor.extract(&pixels).unwrap()
+        );
+    }
+
+    #[test]
+    fn rgba16() {
+        // opaque red next to transparent blue
+        let pixels: [u16; 8] = [65535, 0, 0, 65535, 0, 0, 65535, 0];
+
+        let r = extractor_with(|builder| builder.encoding(PixelEncoding::Rgba))
+            .extract(&pixels)
+            .unwrap();
+
+        assert_eq!(r[0], Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn byte_orders() {
+        let pixels = three_color_image();
+        let wide = widen(&pixels);
+        let le: Vec<u8> = wide.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let be: Vec<u8> = wide.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let extractor = extractor_with(|builder| builder.encoding(PixelEncoding::Rgb));
+
+        let expected = extractor.extract(&pixels).unwrap();
+
+        assert_eq!(
+            extractor
+                .extract(PixelData::U16Bytes(&le, ByteOrder::LittleEndian))
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            extractor
+                .extract(PixelData::U16Bytes(&be, ByteOrder::BigEndian))
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn rounds_to_nearest() {
+        // 0x0788 is just above 7.5 / 255 of the full range, so it rounds up to 8 and lands in the
+        // second bin rather than being truncated to 7
+        let pixels: [u16; 3] = [0x0788, 0, 0];
+
+        let r = extractor_with(|builder| builder.encoding(PixelEncoding::Rgb))
+            .extract(&pixels)
+            .unwrap();
+
+        assert_eq!(r[0], Color::new(12, 4, 4));
+    }
+
+    #[test]
+    fn invalid_byte_length() {
+        let bytes = [0; 7];
+
+        let r = extractor_with(|builder| builder.encoding(PixelEncoding::Gray))
+            .extract(PixelData::U16Bytes(&bytes, ByteOrder::BigEndian));
+
+        assert_eq!(
+            r,
+            Err(palette_extract::PaletteError::InvalidBufferLength { len: 7, stride: 2 })
+        );
+    }
+}
+
+#[cfg(test)]
+mod floating_point {
+    use super::{create_image, extractor_with};
+    use palette_extract::{Color, PixelEncoding, ToneMapping, TransferFunction};
+
+    #[test]
+    fn srgb_matches_u8() {
+        let pixels = create_image(64, |x, y| {
+            if x < 16 {
+                (200, 40, 10)
+            } else if y < 40 {
+                (10, 90, 220)
+            } else {
+                (30, 200, 60)
+            }
+        });
+        let floats: Vec<f32> = pixels.iter().map(|&v| v as f32 / 255.0).collect();
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgb)
+                .transfer_function(TransferFunction::Srgb)
+                .tone_mapping(ToneMapping::Clamp)
+        });
+
+        assert_eq!(
+            extractor.extract(&floats).unwrap(),
+            extractor.extract(&pixels).unwrap()
+        );
+    }
+
+    #[test]
+    fn linear() {
+        let pixels = [0.5, 0.5, 0.5];
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgb)
+                .transfer_function(TransferFunction::Linear)
+                .tone_mapping(ToneMapping::Clamp)
+        });
+
+        // linear 0.5 is about 188 in sRGB
+        assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(188, 188, 188));
+    }
+
+    #[test]
+    fn clamp() {
+        let pixels = [4.0, 0.0, 0.0];
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgb)
+                .transfer_function(TransferFunction::Linear)
+                .tone_mapping(ToneMapping::Clamp)
+        });
+
+        assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn reinhard() {
+        // 4.0 maps to a linear 0.8, which is about 231 in sRGB
+        let pixels = [4.0, 0.0, 0.0];
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgb)
+                .transfer_function(TransferFunction::Linear)
+                .tone_mapping(ToneMapping::Reinhard)
+        });
+
+        assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(228, 4, 4));
+    }
+
+    #[test]
+    fn alpha_is_linear() {
+        // opaque red next to a mostly transparent blue pixel
+        let pixels = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.2];
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .transfer_function(TransferFunction::Srgb)
+                .tone_mapping(ToneMapping::Clamp)
+        });
+
+        let swatches = extractor.extract_swatches(&pixels).unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(252, 4, 4));
+        assert_eq!(swatches[0].population, 1);
+    }
+
+    #[test]
+    fn non_finite() {
+        let pixels = [f32::NAN, f32::INFINITY, f32::NEG_INFINITY];
+
+        for tone_mapping in [ToneMapping::Clamp, ToneMapping::Reinhard] {
+            let extractor = extractor_with(|builder| {
+                builder
+                    .encoding(PixelEncoding::Rgb)
+                    .transfer_function(TransferFunction::Linear)
+                    .tone_mapping(tone_mapping)
+            });
+
+            assert_eq!(extractor.dominant_color(&pixels).unwrap(), Color::new(4, 252, 4));
+        }
+    }
+}
+
+#[cfg(test)]
+mod image_view {
+    use super::{extractor, three_color_image};
+    use palette_extract::{Color, ImageView, PaletteError, PixelEncoding};
+
+    /// Pads each row of a square RGB image with `padding` bytes of garbage.
+    fn pad(pixels: &[u8], dim: usize, padding: usize) -> Vec<u8> {
+        pixels
+            .chunks(dim * 3)
+            .flat_map(|row| row.iter().copied().chain(std::iter::repeat_n(77, padding)))
+            .collect()
+    }
+
+    #[test]
+    fn padding_is_ignored() {
+        let pixels = three_color_image();
+        let padded = pad(&pixels, 64, 13);
+
+        let view = ImageView::with_bytes_per_row(&padded, PixelEncoding::Rgb, 64, 64, 64 * 3 + 13)
+            .unwrap();
+
+        let swatches = extractor().extract_swatches(view).unwrap();
+
+        assert_eq!(swatches, extractor().extract_swatches(&pixels).unwrap());
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 64 * 64);
+    }
+
+    #[test]
+    fn unpadded_last_row() {
+        let pixels = three_color_image();
+        let mut padded = pad(&pixels, 64, 4);
+        padded.truncate(padded.len() - 4);
+
+        let view =
+            ImageView::with_bytes_per_row(&padded, PixelEncoding::Rgb, 64, 64, 64 * 3 + 4).unwrap();
+
+        assert_eq!(
+            extractor().extract(view).unwrap(),
+            extractor().extract(&pixels).unwrap()
+        );
+    }
+
+    #[test]
+    fn sixteen_bit_rows() {
+        // a 1x2 RGBA image, each row padded with a transparent pixel
+        let pixels: [u16; 16] = [
+            65535, 0, 0, 65535, 0, 0, 0, 0, //
+            65535, 0, 0, 65535, 0, 0, 0, 0,
+        ];
+
+        let view = ImageView::with_bytes_per_row(&pixels, PixelEncoding::Rgba, 1, 2, 16).unwrap();
+
+        let swatches = extractor().extract_swatches(view).unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(252, 4, 4));
+        assert_eq!(swatches[0].population, 2);
+    }
+
+    #[test]
+    fn own_encoding() {
+        let pixels: [u8; 6] = [0, 0, 255, 0, 0, 255];
+
+        let view = ImageView::new(&pixels, PixelEncoding::Bgr, 2, 1).unwrap();
+
+        assert_eq!(extractor().dominant_color(view).unwrap(), Color::new(252, 4, 4));
+    }
+
+    #[test]
+    fn invalid_row_stride() {
+        let pixels = [0u8; 64];
+        let wide = [0u16; 64];
+
+        assert_eq!(
+            ImageView::with_bytes_per_row(&pixels, PixelEncoding::Rgb, 4, 2, 11).unwrap_err(),
+            PaletteError::InvalidRowStride { bytes_per_row: 11 }
+        );
+        assert_eq!(
+            ImageView::with_bytes_per_row(&wide, PixelEncoding::Rgb, 4, 2, 25).unwrap_err(),
+            PaletteError::InvalidRowStride { bytes_per_row: 25 }
+        );
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let pixels = [0u8; 20];
+
+        assert_eq!(
+            ImageView::with_bytes_per_row(&pixels, PixelEncoding::Rgb, 2, 2, 16).unwrap_err(),
+            PaletteError::BufferTooSmall {
+                len: 20,
+                required: 22
+            }
+        );
+    }
+
+    #[test]
+    fn overflowing_dimensions() {
+        let pixels = [0u8; 12];
+        let huge = usize::MAX / 3 + 1;
+
+        assert_eq!(
+            ImageView::new(&pixels, PixelEncoding::Rgb, huge, 1).unwrap_err(),
+            PaletteError::BufferTooSmall {
+                len: 12,
+                required: usize::MAX
+            }
+        );
+        assert_eq!(
+            ImageView::with_bytes_per_row(&pixels, PixelEncoding::Rgb, huge, 1, 12).unwrap_err(),
+            PaletteError::InvalidRowStride { bytes_per_row: 12 }
+        );
+        assert_eq!(
+            ImageView::with_bytes_per_row(&pixels, PixelEncoding::Rgb, 1, huge, 6).unwrap_err(),
+            PaletteError::BufferTooSmall {
+                len: 12,
+                required: usize::MAX
+            }
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let view = ImageView::new(&[0u8; 0], PixelEncoding::Rgb, 0, 0).unwrap();
+
+        assert_eq!(extractor().extract(view), Err(PaletteError::EmptyInput));
+    }
+}
+
+#[cfg(test)]
+mod region {
+    use super::{create_image, extractor};
+    use palette_extract::{Color, ImageView, PaletteError, PixelEncoding, Rect};
+
+    /// A 64x64 image, with a 10x20 blue rectangle at (30, 12) on top of red.
+    /// Note `create_image` lays the image out column by column, so `x` is the row here.
+    fn image() -> Vec<u8> {
+        create_image(64, |row, column| {
+            if (12..32).contains(&row) && (30..40).contains(&column) {
+                (0, 0, 255)
+            } else {
+                (255, 0, 0)
+            }
+        })
+    }
+
+    #[test]
+    fn samples_only_inside() {
+        let pixels = image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 64, 64).unwrap();
+
+        let swatches = extractor()
+            .extract_swatches(view.region(Rect::new(30, 12, 10, 20)).unwrap())
+            .unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(4, 4, 252));
+        assert_eq!(swatches[0].population, 200);
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 200);
+    }
+
+    #[test]
+    fn straddling_edge() {
+        let pixels = image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 64, 64).unwrap();
+
+        let swatches = extractor()
+            .extract_swatches(view.region(Rect::new(35, 10, 29, 4)).unwrap())
+            .unwrap();
+
+        // rows 10 and 11 are all red, rows 12 and 13 have 5 blue pixels each
+        let blue = swatches.iter().find(|s| s.color == Color::new(4, 4, 252)).unwrap();
+        assert_eq!(blue.population, 10);
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 29 * 4);
+    }
+
+    #[test]
+    fn nested() {
+        let pixels = image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 64, 64).unwrap();
+
+        let outer = view.region(Rect::new(20, 10, 30, 30)).unwrap();
+        let inner = outer.region(Rect::new(10, 2, 10, 20)).unwrap();
+
+        assert_eq!(
+            extractor().extract_swatches(inner).unwrap(),
+            extractor()
+                .extract_swatches(view.region(Rect::new(30, 12, 10, 20)).unwrap())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let pixels = image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 64, 64).unwrap();
+
+        assert_eq!(
+            view.region(Rect::new(60, 0, 5, 5)).unwrap_err(),
+            PaletteError::RegionOutOfBounds
+        );
+        assert_eq!(
+            view.region(Rect::new(0, usize::MAX, 1, 1)).unwrap_err(),
+            PaletteError::RegionOutOfBounds
+        );
+    }
+}
+
+#[cfg(test)]
+mod weights {
+    use super::{create_image, extractor, extractor_with};
+    use palette_extract::{Color, ImageView, PaletteError, PixelEncoding, PixelFilter, Rect};
+
+    /// A 16x16 image, red on the left half and blue on the right half.
+    fn image() -> Vec<u8> {
+        create_image(16, |_, column| if column < 8 { (255, 0, 0) } else { (0, 0, 255) })
+    }
+
+    fn left_half<T: Copy>(inside: T, outside: T) -> Vec<T> {
+        (0..256)
+            .map(|i| if i % 16 < 8 { inside } else { outside })
+            .collect()
+    }
+
+    const RED: Color = Color { r: 252, g: 4, b: 4 };
+    const BLUE: Color = Color { r: 4, g: 4, b: 252 };
+
+    #[test]
+    fn masks() {
+        let pixels = image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 16, 16).unwrap();
+
+        let byte_mask = left_half(255u8, 0);
+        let bool_mask = left_half(true, false);
+
+        for view in [
+            view.with_weights(&byte_mask).unwrap(),
+            view.with_weights(&bool_mask).unwrap(),
+        ] {
+            let swatches = extractor().extract_swatches(view).unwrap();
+
+            assert_eq!(swatches[0].color, RED);
+            assert_eq!(swatches[0].population, 128);
+            assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 128);
+        }
+    }
+
+    #[test]
+    fn weights() {
+        let pixels = image();
+        let weights = left_half(1.0f32, 0.25);
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 16, 16)
+            .unwrap()
+            .with_weights(&weights)
+            .unwrap();
+
+        let swatches = extractor().extract_swatches(view).unwrap();
+
+        let red = swatches.iter().find(|s| s.color == RED).unwrap();
+        let blue = swatches.iter().find(|s| s.color == BLUE).unwrap();
+        assert_eq!(red.population, 128);
+        assert_eq!(blue.population, 32);
+        assert_eq!(red.proportion, 0.8);
+        assert_eq!(blue.proportion, 0.2);
+    }
+
+    #[test]
+    fn out_of_range_weights() {
+        let pixels = image();
+        let weights = left_half(7.0f32, f32::NAN);
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 16, 16)
+            .unwrap()
+            .with_weights(&weights)
+            .unwrap();
+
+        let swatches = extractor().extract_swatches(view).unwrap();
+
+        assert_eq!(swatches[0].color, RED);
+        assert_eq!(swatches[0].population, 128);
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 128);
+    }
+
+    #[test]
+    fn filter_applies_on_top() {
+        let pixels = create_image(16, |_, column| {
+            if column < 8 { (255, 255, 255) } else { (0, 0, 255) }
+        });
+        let mask = left_half(1u8, 0);
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 16, 16)
+            .unwrap()
+            .with_weights(&mask)
+            .unwrap();
+
+        assert_eq!(
+            extractor_with(|builder| builder.pixel_filter(PixelFilter::default())).extract(view),
+            Err(PaletteError::NoPixels)
+        );
+    }
+
+    #[test]
+    fn cropped_with_region() {
+        let pixels = image();
+        let mask = left_half(false, true);
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 16, 16)
+            .unwrap()
+            .with_weights(&mask)
+            .unwrap();
+
+        // the region straddles both halves, but only the blue half is unmasked
+        let region = view.region(Rect::new(4, 2, 8, 4)).unwrap();
+        let swatches = extractor().extract_swatches(region).unwrap();
+
+        assert_eq!(swatches[0].color, BLUE);
+        assert_eq!(swatches[0].population, 16);
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 16);
+    }
+
+    #[test]
+    fn invalid_length() {
+        let pixels = image();
+        let view = ImageView::new(&pixels, PixelEncoding::Rgb, 16, 16).unwrap();
+
+        assert_eq!(
+            view.with_weights(&[1.0f32; 255]).unwrap_err(),
+            PaletteError::InvalidWeightsLength {
+                len: 255,
+                required: 256
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod custom_filter {
+    use super::{create_image, extractor_with};
+    use palette_extract::{Color, PaletteError, Pixel, PixelEncoding, PixelFilter, PixelPredicate};
+    use std::sync::Arc;
+
+    #[test]
+    fn excludes_black_border() {
+        // a blue image with a 4 pixel black border, where the border is the majority
+        let pixels = create_image(12, |x, y| {
+            if (4..8).contains(&x) && (4..8).contains(&y) {
+                (0, 0, 255)
+            } else {
+                (0, 0, 0)
+            }
+        });
+
+        let filter = PixelFilter::custom(|p| p.r > 10 || p.g > 10 || p.b > 10);
+        let swatches =
+            extractor_with(|builder| builder.encoding(PixelEncoding::Rgb).pixel_filter(filter))
+                .extract_swatches(&pixels)
+                .unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(4, 4, 252));
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 16);
+    }
+
+    #[test]
+    fn receives_alpha() {
+        let pixels: [u8; 16] = [255, 0, 0, 200, 255, 0, 0, 200, 255, 0, 0, 200, 0, 0, 255, 255];
+
+        let filter = PixelFilter::custom(|p| p.a == 255);
+
+        assert_eq!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::Rgba).pixel_filter(filter))
+                .dominant_color(&pixels),
+            Ok(Color::new(4, 4, 252))
+        );
+    }
+
+    #[test]
+    fn never_receives_transparent_pixels() {
+        let pixels: [u8; 8] = [255, 0, 0, 0, 0, 0, 255, 255];
+
+        let filter = PixelFilter::custom(|p| {
+            assert!(p.a > 125);
+            true
+        });
+
+        assert_eq!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::Rgba).pixel_filter(filter))
+                .dominant_color(&pixels),
+            Ok(Color::new(4, 4, 252))
+        );
+    }
+
+    #[test]
+    fn discarding_everything() {
+        let pixels: [u8; 6] = [255, 0, 0, 0, 0, 255];
+
+        assert_eq!(
+            extractor_with(|builder| {
+                builder.encoding(PixelEncoding::Rgb).pixel_filter(PixelFilter::custom(|_| false))
+            })
+            .extract(&pixels),
+            Err(PaletteError::NoPixels)
+        );
+    }
+
+    struct NearGray(u8);
+
+    impl PixelPredicate for NearGray {
+        fn keep(&self, p: Pixel) -> bool {
+            let max = p.r.max(p.g).max(p.b);
+            let min = p.r.min(p.g).min(p.b);
+            max - min > self.0
+        }
+    }
+
+    #[test]
+    fn trait_predicate() {
+        let pixels = create_image(8, |x, _| if x < 6 { (128, 130, 126) } else { (0, 200, 0) });
+
+        let filter = PixelFilter::Custom(Arc::new(NearGray(16)));
+        let swatches =
+            extractor_with(|builder| builder.encoding(PixelEncoding::Rgb).pixel_filter(filter))
+                .extract_swatches(&pixels)
+                .unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(4, 204, 4));
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 16);
+    }
+
+    #[test]
+    fn equality() {
+        let filter = PixelFilter::custom(|_| true);
+
+        assert_eq!(filter, filter.clone());
+        assert_ne!(filter, PixelFilter::custom(|_| true));
+        assert_ne!(filter, PixelFilter::None);
+        assert_eq!(PixelFilter::White { threshold: 250 }, PixelFilter::default());
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let pixels: [u8; 6] = [255, 0, 0, 255, 0, 0];
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgb)
+                .pixel_filter(PixelFilter::custom(|p| p.r > 0))
+        });
+
+        let color = std::thread::scope(|s| {
+            s.spawn(|| extractor.dominant_color(&pixels)).join().unwrap()
+        });
+
+        assert_eq!(color, Ok(Color::new(252, 4, 4)));
+    }
+}
+
+#[cfg(test)]
+mod thresholds {
+    use super::extractor_with;
+    use palette_extract::{
+        Color, PaletteError, PaletteExtractor, PixelEncoding, PixelFilter, Quality,
+    };
+
+    fn kept(pixel_filter: PixelFilter, alpha_threshold: u8, pixel: [u8; 4]) -> bool {
+        match extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .pixel_filter(pixel_filter)
+                .alpha_threshold(alpha_threshold)
+        })
+        .dominant_color(&pixel)
+        {
+            Ok(_) => true,
+            Err(PaletteError::NoPixels) => false,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    #[test]
+    fn white() {
+        let white = |threshold| PixelFilter::White { threshold };
+
+        assert!(kept(white(250), 125, [250, 250, 250, 255]));
+        assert!(!kept(white(250), 125, [251, 251, 251, 255]));
+        assert!(kept(white(250), 125, [251, 251, 250, 255]));
+
+        // off-white paper
+        assert!(kept(white(250), 125, [242, 241, 240, 255]));
+        assert!(!kept(white(239), 125, [242, 241, 240, 255]));
+
+        assert!(!kept(white(0), 125, [1, 1, 1, 255]));
+        assert!(kept(white(0), 125, [1, 0, 1, 255]));
+        assert!(kept(white(255), 125, [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn black() {
+        let black = |threshold| PixelFilter::Black { threshold };
+
+        assert!(!kept(black(5), 125, [4, 4, 4, 255]));
+        assert!(kept(black(5), 125, [5, 5, 5, 255]));
+        assert!(kept(black(5), 125, [4, 5, 4, 255]));
+
+        assert!(!kept(black(255), 125, [254, 254, 254, 255]));
+        assert!(kept(black(255), 125, [255, 254, 254, 255]));
+        assert!(kept(black(0), 125, [0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn alpha() {
+        assert!(!kept(PixelFilter::None, 125, [255, 0, 0, 125]));
+        assert!(kept(PixelFilter::None, 125, [255, 0, 0, 126]));
+
+        assert!(!kept(PixelFilter::None, 0, [255, 0, 0, 0]));
+        assert!(kept(PixelFilter::None, 0, [255, 0, 0, 1]));
+
+        assert!(!kept(PixelFilter::None, 254, [255, 0, 0, 254]));
+        assert!(kept(PixelFilter::None, 254, [255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn rejects_alpha_threshold_255() {
+        let result = PaletteExtractor::builder()
+            .encoding(PixelEncoding::Rgb)
+            .alpha_threshold(255)
+            .build();
+
+        assert_eq!(result.unwrap_err(), PaletteError::InvalidAlphaThreshold);
+
+        // the highest threshold still keeps opaque pixels, with or without an alpha channel
+        let rgb = PaletteExtractor::builder()
+            .encoding(PixelEncoding::Rgb)
+            .quality(Quality::new(1))
+            .alpha_threshold(254)
+            .build()
+            .unwrap();
+        assert_eq!(rgb.extract(&[10u8, 20, 30]), Ok(vec![Color::new(12, 20, 28)]));
+    }
+
+    #[test]
+    fn default_alpha_threshold() {
+        let pixels: [u8; 8] = [255, 0, 0, 125, 0, 0, 255, 126];
+
+        let extractor = PaletteExtractor::builder()
+            .encoding(PixelEncoding::Rgba)
+            .quality(Quality::new(1))
+            .build()
+            .unwrap();
+
+        assert_eq!(extractor.dominant_color(&pixels), Ok(Color::new(4, 4, 252)));
+    }
+
+    #[test]
+    fn soft_edges() {
+        // a logo with anti-aliased edges, where most of the color is in translucent pixels
+        let pixels: [u8; 16] = [0, 0, 255, 255, 255, 0, 0, 60, 255, 0, 0, 60, 255, 0, 0, 60];
+
+        let swatches = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .pixel_filter(PixelFilter::None)
+                .alpha_threshold(50)
+        })
+        .extract_swatches(&pixels)
+        .unwrap();
+
+        let red = swatches.iter().find(|s| s.color == Color::new(252, 4, 4)).unwrap();
+        assert_eq!(red.population, 3);
+        assert_eq!(red.proportion, 0.75);
+    }
+}
+
+#[cfg(test)]
+mod alpha_policy {
+    use super::extractor_with;
+    use palette_extract::{
+        AlphaPolicy, Color, MaxColors, PaletteError, PaletteExtractor, PixelEncoding, PixelFilter,
+    };
+
+    // a mostly translucent red icon, with a single opaque blue pixel
+    const ICON: [u8; 20] = [
+        255, 0, 0, 102, 255, 0, 0, 102, 255, 0, 0, 102, 255, 0, 0, 0, 0, 0, 255, 255,
+    ];
+
+    #[test]
+    fn discard_is_default() {
+        let swatches = extractor_with(|builder| {
+            builder.encoding(PixelEncoding::Rgba).alpha_policy(AlphaPolicy::default())
+        })
+        .extract_swatches(&ICON)
+        .unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(4, 4, 252));
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn weight() {
+        let swatches = extractor_with(|builder| {
+            builder.encoding(PixelEncoding::Rgba).alpha_policy(AlphaPolicy::Weight)
+        })
+        .extract_swatches(&ICON)
+        .unwrap();
+
+        let red = swatches.iter().find(|s| s.color == Color::new(252, 4, 4)).unwrap();
+        let blue = swatches.iter().find(|s| s.color == Color::new(4, 4, 252)).unwrap();
+
+        // each red pixel counts as 0.4 of a pixel, and the transparent one not at all
+        assert_eq!(red.proportion, 306.0 / 561.0);
+        assert_eq!(blue.proportion, 255.0 / 561.0);
+        assert_eq!(red.population, 1);
+        assert_eq!(blue.population, 1);
+    }
+
+    #[test]
+    fn weight_ignores_threshold() {
+        let pixels: [u8; 4] = [255, 0, 0, 1];
+
+        let extractor = PaletteExtractor::builder()
+            .encoding(PixelEncoding::Rgba)
+            .alpha_policy(AlphaPolicy::Weight)
+            .alpha_threshold(200)
+            .build()
+            .unwrap();
+
+        assert_eq!(extractor.dominant_color(&pixels), Ok(Color::new(252, 4, 4)));
+        assert_eq!(extractor.extract(&[255u8, 0, 0, 0]), Err(PaletteError::NoPixels));
+    }
+
+    #[test]
+    fn composite() {
+        let black = AlphaPolicy::Composite(Color::new(0, 0, 0));
+        let swatches = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .alpha_policy(black)
+                .max_colors(MaxColors::default())
+        })
+        .extract_swatches(&ICON)
+        .unwrap();
+
+        // red at 40% over black is (102, 0, 0), and the transparent pixel is black
+        let colors: Vec<Color> = swatches
+            .iter()
+            .filter(|s| s.population > 0)
+            .map(|s| s.color)
+            .collect();
+        assert!(colors.contains(&Color::new(100, 4, 4)));
+        assert!(colors.contains(&Color::new(4, 4, 4)));
+        assert!(colors.contains(&Color::new(4, 4, 252)));
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 5);
+    }
+
+    #[test]
+    fn composite_then_filter() {
+        let white = AlphaPolicy::Composite(Color::new(255, 255, 255));
+        let pixels: [u8; 12] = [255, 0, 0, 0, 0, 0, 255, 0, 0, 0, 255, 128];
+
+        // the transparent pixels become white, and are removed by the white filter
+        let swatches = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .alpha_policy(white)
+                .pixel_filter(PixelFilter::default())
+        })
+        .extract_swatches(&pixels)
+        .unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(124, 124, 252));
+        assert_eq!(swatches.iter().map(|s| s.population).sum::<u64>(), 1);
+    }
+}
+
+#[cfg(test)]
+mod premultiplied {
+    use super::{create_image, extractor_with};
+    use palette_extract::{AlphaPolicy, Color, Pixel, PixelEncoding};
+
+    /// RGBA pixels with an alpha of 160, and their premultiplied counterparts.
+    fn images() -> (Vec<u8>, Vec<u8>) {
+        let rgb = create_image(32, |x, _| if x < 12 { (204, 44, 12) } else { (12, 92, 220) });
+
+        let straight = rgb.chunks(3).flat_map(|p| [p[0], p[1], p[2], 160]).collect();
+        let premultiplied = rgb
+            .chunks(3)
+            .flat_map(|p| {
+                let pm = |c: u8| ((c as u32 * 160 + 127) / 255) as u8;
+                [pm(p[0]), pm(p[1]), pm(p[2]), 160]
+            })
+            .collect();
+
+        (straight, premultiplied)
+    }
+
+    #[test]
+    fn matches_straight_alpha() {
+        let (straight, premultiplied) = images();
+
+        let expected = extractor_with(|builder| builder.encoding(PixelEncoding::Rgba))
+            .extract(&straight)
+            .unwrap();
+
+        assert_eq!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::RgbaPremultiplied))
+                .extract(&premultiplied)
+                .unwrap(),
+            expected
+        );
+
+        // without un-premultiplying, the colors are darker
+        assert_ne!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::Rgba))
+                .extract(&premultiplied)
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn layouts() {
+        let (_, premultiplied) = images();
+        let reorder = |order: [usize; 4]| -> Vec<u8> {
+            premultiplied.chunks(4).flat_map(|p| order.map(|c| p[c])).collect()
+        };
+
+        let expected = extractor_with(|builder| builder.encoding(PixelEncoding::RgbaPremultiplied))
+            .extract(&premultiplied)
+            .unwrap();
+
+        for (order, encoding) in [
+            ([2, 1, 0, 3], PixelEncoding::BgraPremultiplied),
+            ([3, 0, 1, 2], PixelEncoding::ArgbPremultiplied),
+            ([3, 2, 1, 0], PixelEncoding::AbgrPremultiplied),
+        ] {
+            assert_eq!(
+                extractor_with(|builder| builder.encoding(encoding))
+                    .extract(&reorder(order))
+                    .unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn gray_alpha() {
+        let pixels: [u8; 4] = [100, 200, 100, 200];
+
+        assert_eq!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::GrayAlphaPremultiplied))
+                .dominant_color(&pixels),
+            Ok(Color::new(132, 132, 132))
+        );
+    }
+
+    #[test]
+    fn transparent_pixels() {
+        // premultiplied pixels with an alpha of 0 are transparent, whatever their color channels
+        let pixels: [u8; 16] = [0, 0, 0, 0, 90, 90, 90, 0, 0, 0, 0, 0, 255, 0, 0, 255];
+
+        let discarded = extractor_with(|builder| builder.encoding(PixelEncoding::RgbaPremultiplied))
+            .extract_swatches(&pixels)
+            .unwrap();
+        assert_eq!(discarded[0].color, Color::new(252, 4, 4));
+        assert_eq!(discarded.iter().map(|s| s.population).sum::<u64>(), 1);
+
+        let composited = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::RgbaPremultiplied)
+                .alpha_policy(AlphaPolicy::Composite(Color::new(0, 0, 255)))
+        });
+        assert_eq!(composited.dominant_color(&pixels), Ok(Color::new(4, 4, 252)));
+    }
+
+    #[test]
+    fn out_of_range_channels_saturate() {
+        let pixels: [u8; 4] = [200, 0, 0, 150];
+
+        assert_eq!(
+            PixelEncoding::RgbaPremultiplied.extract_pixel(&pixels, 0),
+            Pixel::new_rgba(255, 0, 0, 150)
+        );
+    }
+
+    #[test]
+    fn extract_pixel() {
+        let pixels: [u8; 8] = [255, 255, 255, 255, 128, 64, 0, 128];
+
+        assert_eq!(
+            PixelEncoding::RgbaPremultiplied.extract_pixel(&pixels, 1),
+            Pixel::new_rgba(255, 128, 0, 128)
+        );
+        assert!(PixelEncoding::RgbaPremultiplied.is_premultiplied());
+        assert!(!PixelEncoding::Rgba.is_premultiplied());
+    }
+
+    #[test]
+    fn sixteen_bit() {
+        let pixels: [u16; 8] = [32768, 16384, 0, 32768, 32768, 16384, 0, 32768];
+
+        assert_eq!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::RgbaPremultiplied))
+                .dominant_color(&pixels),
+            Ok(Color::new(252, 132, 4))
+        );
+    }
+
+    #[test]
+    fn floating_point() {
+        let straight: [f32; 8] = [0.5, 0.25, 0.0, 0.5, 0.5, 0.25, 0.0, 0.5];
+        let premultiplied: [f32; 8] = [0.25, 0.125, 0.0, 0.5, 0.25, 0.125, 0.0, 0.5];
+
+        assert_eq!(
+            extractor_with(|builder| builder.encoding(PixelEncoding::RgbaPremultiplied))
+                .extract(&premultiplied),
+            extractor_with(|builder| builder.encoding(PixelEncoding::Rgba)).extract(&straight)
+        );
+    }
+}
+
+#[cfg(test)]
+mod histogram_bits {
+    use super::{extractor, extractor_with};
+    use palette_extract::{Color, ColorBox, PaletteError, PaletteExtractor};
+
+    #[test]
+    fn eight_bits_returns_exact_colors_on_flat_images() {
+        let pixels: [u8; 12] = [18, 52, 86, 18, 52, 86, 18, 52, 86, 18, 52, 86];
+
+        let swatches = extractor_with(|builder| builder.histogram_bits(8))
+            .extract_swatches(&pixels)
+            .unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(18, 52, 86));
+        assert_eq!(swatches[0].population, 4);
+        assert_eq!(
+            swatches[0].bounds,
+            ColorBox {
+                min: Color::new(18, 52, 86),
+                max: Color::new(18, 52, 86),
+            }
+        );
+    }
+
+    #[test]
+    fn eight_bits_returns_exact_colors_on_two_color_images() {
+        let pixels: [u8; 12] = [18, 52, 86, 18, 52, 86, 200, 100, 37, 255, 255, 255];
+
+        let palette = extractor_with(|builder| builder.histogram_bits(8)).extract(&pixels).unwrap();
+
+        assert!(palette.contains(&Color::new(18, 52, 86)));
+        assert!(palette.contains(&Color::new(200, 100, 37)));
+        assert!(palette.contains(&Color::new(255, 255, 255)));
+    }
+
+    #[test]
+    fn eight_bits_dominant_color() {
+        let pixels: [u8; 9] = [0, 0, 1, 0, 0, 1, 0, 0, 0];
+
+        assert_eq!(
+            extractor_with(|builder| builder.histogram_bits(8)).dominant_color(&pixels),
+            Ok(Color::new(0, 0, 1))
+        );
+    }
+
+    #[test]
+    fn fewer_bits_use_wider_bins() {
+        let pixels: [u8; 3] = [18, 52, 86];
+
+        assert_eq!(
+            extractor_with(|builder| builder.histogram_bits(4)).dominant_color(&pixels),
+            Ok(Color::new(24, 56, 88))
+        );
+        assert_eq!(
+            extractor_with(|builder| builder.histogram_bits(5)).dominant_color(&pixels),
+            Ok(Color::new(20, 52, 84))
+        );
+        assert_eq!(
+            extractor_with(|builder| builder.histogram_bits(6)).dominant_color(&pixels),
+            Ok(Color::new(18, 54, 86))
+        );
+        assert_eq!(
+            extractor_with(|builder| builder.histogram_bits(7)).dominant_color(&pixels),
+            Ok(Color::new(19, 53, 87))
+        );
+    }
+
+    #[test]
+    fn default_is_five_bits() {
+        let pixels: [u8; 6] = [18, 52, 86, 200, 100, 37];
+
+        let default = extractor();
+
+        assert_eq!(
+            default.extract(&pixels),
+            extractor_with(|builder| builder.histogram_bits(5)).extract(&pixels)
+        );
+    }
+
+    #[test]
+    fn rejects_bits_outside_of_4_to_8() {
+        for bits in [0, 3, 9, u8::MAX] {
+            let result = PaletteExtractor::builder().histogram_bits(bits).build();
+
+            assert_eq!(result.unwrap_err(), PaletteError::InvalidHistogramBits { bits });
+        }
+    }
+}
+
+#[cfg(test)]
+mod color_average {
+    use super::{extractor, extractor_with};
+    use palette_extract::{AlphaPolicy, Color, ColorAverage, PixelEncoding};
+
+    #[test]
+    fn pixel_mean_returns_exact_colors() {
+        let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 0, 255];
+
+        let swatches = extractor_with(|builder| builder.color_average(ColorAverage::PixelMean))
+            .extract_swatches(&pixels)
+            .unwrap();
+
+        let red = swatches.iter().find(|s| s.color == Color::new(255, 0, 0)).unwrap();
+        assert_eq!(red.population, 3);
+        assert!(swatches.iter().any(|s| s.color == Color::new(0, 0, 255)));
+    }
+
+    #[test]
+    fn bin_center_is_the_default() {
+        let pixels: [u8; 12] = [255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 0, 255];
+
+        assert_eq!(
+            extractor().extract(&pixels),
+            extractor_with(|builder| builder.color_average(ColorAverage::BinCenter))
+                .extract(&pixels)
+        );
+        assert_eq!(
+            extractor_with(|builder| builder.color_average(ColorAverage::BinCenter))
+                .dominant_color(&pixels),
+            Ok(Color::new(252, 4, 4))
+        );
+    }
+
+    #[test]
+    fn pixel_mean_averages_pixels_in_the_same_cell() {
+        let pixels: [u8; 9] = [16, 16, 16, 18, 18, 18, 20, 23, 16];
+
+        assert_eq!(
+            extractor_with(|builder| builder.color_average(ColorAverage::PixelMean))
+                .dominant_color(&pixels),
+            Ok(Color::new(18, 19, 17))
+        );
+        assert_eq!(
+            extractor_with(|builder| builder.color_average(ColorAverage::BinCenter))
+                .dominant_color(&pixels),
+            Ok(Color::new(20, 20, 20))
+        );
+    }
+
+    #[test]
+    fn pixel_mean_is_weighted() {
+        let pixels: [u8; 8] = [16, 16, 16, 255, 22, 22, 22, 85];
+
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .alpha_policy(AlphaPolicy::Weight)
+                .color_average(ColorAverage::PixelMean)
+        });
+
+        // (16 * 255 + 22 * 85) / 340 = 17.5
+        assert_eq!(extractor.dominant_color(&pixels), Ok(Color::new(18, 18, 18)));
+    }
+}
+
+#[cfg(test)]
+mod exact_colors {
+    use super::{extractor, extractor_with};
+    use palette_extract::{
+        AlphaPolicy, Color, ColorBox, ExactColors, MaxColors, PaletteError, PaletteExtractor,
+        PixelEncoding, Swatch,
+    };
+
+    fn swatch(color: Color, population: u64, proportion: f32) -> Swatch {
+        Swatch {
+            color,
+            population,
+            proportion,
+            bounds: ColorBox {
+                min: color,
+                max: color,
+            },
+        }
+    }
+
+    #[test]
+    fn auto_returns_exact_colors() {
+        let pixels: [u8; 12] = [255, 0, 0, 0, 0, 255, 255, 0, 0, 255, 0, 0];
+
+        assert_eq!(
+            extractor_with(|builder| builder.exact_colors(ExactColors::Auto))
+                .extract_swatches(&pixels),
+            Ok(vec![
+                swatch(Color::new(255, 0, 0), 3, 0.75),
+                swatch(Color::new(0, 0, 255), 1, 0.25),
+            ])
+        );
+    }
+
+    #[test]
+    fn auto_only_applies_up_to_max_colors() {
+        let pixels: [u8; 9] = [255, 0, 0, 0, 255, 0, 0, 0, 255];
+
+        assert_eq!(
+            extractor_with(|builder| {
+                builder.exact_colors(ExactColors::Auto).max_colors(MaxColors::new(2))
+            })
+            .extract_swatches(&pixels),
+            extractor_with(|builder| {
+                builder.exact_colors(ExactColors::Never).max_colors(MaxColors::new(2))
+            })
+            .extract_swatches(&pixels)
+        );
+        assert_eq!(
+            extractor_with(|builder| {
+                builder.exact_colors(ExactColors::Auto).max_colors(MaxColors::new(3))
+            })
+            .extract(&pixels),
+            Ok(vec![Color::new(0, 0, 255), Color::new(0, 255, 0), Color::new(255, 0, 0)])
+        );
+    }
+
+    #[test]
+    fn always_returns_the_most_populous_exact_colors() {
+        let pixels: [u8; 18] = [1, 2, 3, 4, 5, 6, 1, 2, 3, 7, 8, 9, 4, 5, 6, 1, 2, 3];
+
+        assert_eq!(
+            extractor_with(|builder| {
+                builder.exact_colors(ExactColors::Always).max_colors(MaxColors::new(2))
+            })
+            .extract(&pixels),
+            Ok(vec![Color::new(1, 2, 3), Color::new(4, 5, 6)])
+        );
+    }
+
+    #[test]
+    fn ties_are_broken_by_color() {
+        let pixels: [u8; 9] = [9, 9, 9, 1, 1, 1, 5, 5, 5];
+
+        assert_eq!(
+            extractor_with(|builder| {
+                builder.exact_colors(ExactColors::Always).max_colors(MaxColors::new(3))
+            })
+            .extract(&pixels),
+            Ok(vec![Color::new(1, 1, 1), Color::new(5, 5, 5), Color::new(9, 9, 9)])
+        );
+    }
+
+    #[test]
+    fn never_is_the_default() {
+        let pixels: [u8; 12] = [255, 0, 0, 0, 0, 255, 255, 0, 0, 255, 0, 0];
+
+        let default = extractor();
+
+        assert_eq!(
+            default.extract(&pixels),
+            extractor_with(|builder| builder.exact_colors(ExactColors::Never))
+                .extract(&pixels)
+        );
+        assert_eq!(default.dominant_color(&pixels), Ok(Color::new(252, 4, 4)));
+    }
+
+    #[test]
+    fn dominant_color() {
+        let pixels: [u8; 12] = [0, 0, 255, 255, 0, 0, 255, 0, 0, 254, 0, 0];
+
+        assert_eq!(
+            extractor_with(|builder| builder.exact_colors(ExactColors::Auto))
+                .dominant_color(&pixels),
+            Ok(Color::new(255, 0, 0))
+        );
+    }
+
+    #[test]
+    fn weighted_pixels() {
+        let pixels: [u8; 8] = [255, 0, 0, 255, 0, 0, 255, 51];
+
+        let extractor = extractor_with(|builder| {
+            builder
+                .encoding(PixelEncoding::Rgba)
+                .alpha_policy(AlphaPolicy::Weight)
+                .exact_colors(ExactColors::Auto)
+        });
+
+        let swatches = extractor.extract_swatches(&pixels).unwrap();
+
+        assert_eq!(swatches[0].color, Color::new(255, 0, 0));
+        assert_eq!(swatches[0].population, 1);
+        assert_eq!(swatches[1].color, Color::new(0, 0, 255));
+        assert_eq!(swatches[1].population, 0);
+        assert_eq!(swatches[1].proportion, 51.0 / 306.0);
+    }
+
+    #[test]
+    fn errors() {
+        let white: [u8; 3] = [255, 255, 255];
+        let filtered = PaletteExtractor::builder()
+            .exact_colors(ExactColors::Always)
+            .build()
+            .unwrap();
+
+        assert_eq!(filtered.extract(&white), Err(PaletteError::NoPixels));
+        assert_eq!(filtered.extract(&[] as &[u8]), Err(PaletteError::EmptyInput));
+    }
+}
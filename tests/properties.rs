@@ -0,0 +1,182 @@
+use palette_extract::{
+    try_get_palette_with_options, ImageView, MaxColors, PaletteError, PaletteExtractor,
+    PixelEncoding, PixelFilter, Quality, ToneMapping, TransferFunction,
+};
+use proptest::prelude::*;
+
+fn encoding() -> impl Strategy<Value = PixelEncoding> {
+    prop_oneof![
+        Just(PixelEncoding::Rgb),
+        Just(PixelEncoding::Rgba),
+        Just(PixelEncoding::Bgr),
+        Just(PixelEncoding::Bgra),
+        Just(PixelEncoding::Argb),
+        Just(PixelEncoding::Abgr),
+        Just(PixelEncoding::Gray),
+        Just(PixelEncoding::GrayAlpha),
+        Just(PixelEncoding::RgbaPremultiplied),
+        Just(PixelEncoding::BgraPremultiplied),
+        Just(PixelEncoding::ArgbPremultiplied),
+        Just(PixelEncoding::AbgrPremultiplied),
+        Just(PixelEncoding::GrayAlphaPremultiplied),
+    ]
+}
+
+fn transfer_function() -> impl Strategy<Value = TransferFunction> {
+    prop_oneof![Just(TransferFunction::Linear), Just(TransferFunction::Srgb)]
+}
+
+fn tone_mapping() -> impl Strategy<Value = ToneMapping> {
+    prop_oneof![Just(ToneMapping::Clamp), Just(ToneMapping::Reinhard)]
+}
+
+fn pixel_filter() -> impl Strategy<Value = PixelFilter> {
+    prop_oneof![
+        Just(PixelFilter::None),
+        any::<u8>().prop_map(|threshold| PixelFilter::White { threshold }),
+        any::<u8>().prop_map(|threshold| PixelFilter::Black { threshold }),
+    ]
+}
+
+/// Image dimensions, biased towards ones whose products overflow a `usize`.
+fn dimension() -> impl Strategy<Value = usize> {
+    prop_oneof![
+        0..64usize,
+        (usize::MAX / 5)..=(usize::MAX / 3 + 1),
+        any::<usize>(),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn never_panics(
+        pixels in proptest::collection::vec(any::<u8>(), 0..2048),
+        encoding in encoding(),
+        quality in any::<u8>(),
+        max_colors in any::<u8>(),
+        pixel_filter in pixel_filter(),
+    ) {
+        let result = try_get_palette_with_options(
+            &pixels,
+            encoding,
+            Quality::new(quality),
+            MaxColors::new(max_colors),
+            pixel_filter,
+        );
+
+        match result {
+            Ok(palette) => prop_assert!(!palette.is_empty()),
+            Err(PaletteError::EmptyInput) => prop_assert!(pixels.is_empty()),
+            Err(PaletteError::InvalidBufferLength { len, stride }) => {
+                prop_assert_eq!(len, pixels.len());
+                prop_assert_ne!(len % stride as usize, 0);
+            }
+            Err(PaletteError::ZeroQuality) => prop_assert_eq!(quality, 0),
+            Err(PaletteError::ZeroMaxColors) => prop_assert_eq!(max_colors, 0),
+            Err(PaletteError::NoPixels) => {}
+            Err(PaletteError::UncuttableBox) => prop_assert!(false, "median cut failed"),
+            Err(err @ PaletteError::InvalidRowStride { .. })
+            | Err(err @ PaletteError::BufferTooSmall { .. })
+            | Err(err @ PaletteError::InvalidWeightsLength { .. })
+            | Err(err @ PaletteError::RegionOutOfBounds)
+            | Err(err @ PaletteError::InvalidHistogramBits { .. })
+            | Err(err @ PaletteError::InvalidAlphaThreshold) => {
+                prop_assert!(false, "unexpected error for a packed buffer: {}", err)
+            }
+        }
+    }
+
+    #[test]
+    fn opaque_pixels_always_produce_a_palette(
+        pixels in proptest::collection::vec(any::<[u8; 3]>(), 1..1024),
+        quality in 1..=u8::MAX,
+        max_colors in 1..=u8::MAX,
+    ) {
+        let pixels: Vec<u8> = pixels.into_iter().flatten().collect();
+
+        let result = try_get_palette_with_options(
+            &pixels,
+            PixelEncoding::Rgb,
+            Quality::new(quality),
+            MaxColors::new(max_colors),
+            PixelFilter::None,
+        );
+
+        prop_assert!(result.is_ok());
+    }
+
+    #[test]
+    fn any_histogram_bits_produce_a_palette(
+        pixels in proptest::collection::vec(any::<[u8; 3]>(), 1..256),
+        histogram_bits in 4..=6u8,
+        max_colors in 1..=16u8,
+    ) {
+        let pixels: Vec<u8> = pixels.into_iter().flatten().collect();
+
+        let extractor = PaletteExtractor::builder()
+            .quality(Quality::new(1))
+            .max_colors(MaxColors::new(max_colors))
+            .pixel_filter(PixelFilter::None)
+            .histogram_bits(histogram_bits)
+            .build()
+            .unwrap();
+
+        prop_assert!(extractor.extract(&pixels).is_ok());
+    }
+
+    #[test]
+    fn floats_never_panic(
+        pixels in proptest::collection::vec(any::<f32>(), 0..1024),
+        encoding in encoding(),
+        transfer_function in transfer_function(),
+        tone_mapping in tone_mapping(),
+    ) {
+        let extractor = PaletteExtractor::builder()
+            .encoding(encoding)
+            .quality(Quality::new(1))
+            .pixel_filter(PixelFilter::None)
+            .transfer_function(transfer_function)
+            .tone_mapping(tone_mapping)
+            .build()
+            .unwrap();
+
+        let result = extractor.extract(&pixels);
+
+        prop_assert!(!matches!(result, Err(PaletteError::UncuttableBox)));
+    }
+
+    #[test]
+    fn oversized_views_are_rejected(
+        pixels in proptest::collection::vec(any::<u16>(), 0..256),
+        encoding in encoding(),
+        width in dimension(),
+        height in dimension(),
+        bytes_per_row in dimension(),
+    ) {
+        let views = [
+            ImageView::new(&pixels, encoding, width, height),
+            ImageView::with_bytes_per_row(&pixels, encoding, width, height, bytes_per_row),
+        ];
+
+        for view in views {
+            match view {
+                Ok(view) => {
+                    prop_assert!(view.width().saturating_mul(view.height()) <= pixels.len());
+
+                    let extractor = PaletteExtractor::builder()
+                        .quality(Quality::new(1))
+                        .pixel_filter(PixelFilter::None)
+                        .build()
+                        .unwrap();
+                    let _ = extractor.extract(view);
+                }
+                Err(PaletteError::InvalidRowStride { .. }) => {}
+                Err(PaletteError::BufferTooSmall { len, required }) => {
+                    prop_assert_eq!(len, pixels.len());
+                    prop_assert!(required > len);
+                }
+                Err(err) => prop_assert!(false, "unexpected error: {}", err),
+            }
+        }
+    }
+}